@@ -1,4 +1,6 @@
 pub mod move_mode;
 pub mod input;
 pub mod input_context;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod exporter;
+pub mod params;
\ No newline at end of file
@@ -0,0 +1,204 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use crate::math::mesh::Mesh;
+use crate::math::vector::Vector;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Obj,
+    Stl,
+}
+
+pub fn export_meshes(meshes: &[Mesh], path: &Path, format: ExportFormat) -> Result<(), String> {
+    match format {
+        ExportFormat::Obj => write_obj(meshes, path),
+        ExportFormat::Stl => write_stl(meshes, path),
+    }
+}
+
+fn material_name(color: [u8; 3]) -> String {
+    format!("color_{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+// Writes every mesh as its own object, with the stroke colours collected into a sibling .mtl file.
+pub fn write_obj(meshes: &[Mesh], path: &Path) -> Result<(), String> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = match mtl_path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return Err(format!("Invalid export path: {}", path.display())),
+    };
+
+    let mut obj = String::new();
+    let mut materials: Vec<[u8; 3]> = Vec::new();
+    writeln!(obj, "# term3d export").unwrap();
+    writeln!(obj, "mtllib {}", mtl_name).unwrap();
+
+    // OBJ indices are global across objects, so keep counting from the previous mesh
+    let mut vertex_offset = 0;
+    let mut normal_offset = 0;
+    let mut uv_offset = 0;
+    for (i, mesh) in meshes.iter().enumerate() {
+        writeln!(obj, "o object_{}", i).unwrap();
+
+        for v in &mesh.vertexes {
+            writeln!(obj, "v {} {} {}", v.x, v.y, v.z).unwrap();
+        }
+        for n in &mesh.normals {
+            writeln!(obj, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+        }
+        for uv in &mesh.uvs {
+            writeln!(obj, "vt {} {}", uv[0], uv[1]).unwrap();
+        }
+
+        let mut current: Option<[u8; 3]> = None;
+        for face in mesh.faces.iter() {
+            let color = face.stroke.color;
+            if current != Some(color) {
+                if !materials.contains(&color) {
+                    materials.push(color);
+                }
+                writeln!(obj, "usemtl {}", material_name(color)).unwrap();
+                current = Some(color);
+            }
+            let corners: Vec<String> = (0..3).map(|i| {
+                let v = face.verts[i] + vertex_offset + 1;
+                let vt = face.uvs.map(|uvs| uvs[i] + uv_offset + 1);
+                let vn = face.normals.map(|normals| normals[i] + normal_offset + 1);
+                match (vt, vn) {
+                    (Some(vt), Some(vn)) => format!("{}/{}/{}", v, vt, vn),
+                    (Some(vt), None) => format!("{}/{}", v, vt),
+                    (None, Some(vn)) => format!("{}//{}", v, vn),
+                    (None, None) => v.to_string(),
+                }
+            }).collect();
+            writeln!(obj, "f {}", corners.join(" ")).unwrap();
+        }
+        vertex_offset += mesh.vertexes.len();
+        normal_offset += mesh.normals.len();
+        uv_offset += mesh.uvs.len();
+    }
+
+    let mut mtl = String::new();
+    writeln!(mtl, "# term3d export").unwrap();
+    for color in materials {
+        writeln!(mtl, "newmtl {}", material_name(color)).unwrap();
+        writeln!(mtl, "Kd {} {} {}",
+            color[0] as f32 / 255.0, color[1] as f32 / 255.0, color[2] as f32 / 255.0).unwrap();
+    }
+
+    fs::write(path, obj).map_err(|e| e.to_string())?;
+    fs::write(&mtl_path, mtl).map_err(|e| e.to_string())
+}
+
+pub fn write_stl(meshes: &[Mesh], path: &Path) -> Result<(), String> {
+    let count: usize = meshes.iter().map(|m| m.faces.len()).sum();
+    let mut bytes: Vec<u8> = Vec::with_capacity(84 + count * 50);
+
+    let mut header = [0u8; 80];
+    let title = b"term3d export";
+    header[..title.len()].copy_from_slice(title);
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&(count as u32).to_le_bytes());
+
+    for tri in meshes.iter().flat_map(|m| m.triangles()) {
+        let normal = tri.normal();
+        let normal = if normal.len().is_finite() { normal } else { Vector::zero() };
+        push_vector(&mut bytes, normal);
+        for v in tri.verts {
+            push_vector(&mut bytes, v.into());
+        }
+        bytes.extend_from_slice(&stl_color(tri.stroke.color).to_le_bytes());
+    }
+
+    fs::write(path, bytes).map_err(|e| e.to_string())
+}
+
+fn push_vector(bytes: &mut Vec<u8>, v: Vector) {
+    for c in [v.x, v.y, v.z] {
+        bytes.extend_from_slice(&c.to_le_bytes());
+    }
+}
+
+// VisCAM/SolidView convention: 5 bits per channel in the attribute bytes, bit 15 marks the colour as valid
+fn stl_color(color: [u8; 3]) -> u16 {
+    let [r, g, b] = color.map(|c| (c >> 3) as u16);
+    0x8000 | (r << 10) | (g << 5) | b
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::interface::parser::parse_mesh;
+    use crate::math::mesh::{Face, Mesh};
+    use crate::math::vector::Vector;
+    use crate::rendering::stroke::Stroke;
+    use super::{write_obj, write_stl};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("term3d_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // A mesh whose faces alternate between two colours, so material switches get exercised
+    fn two_tone(mesh: Mesh, colors: [[u8; 3]; 2]) -> Mesh {
+        let faces: Vec<Face> = mesh.faces.iter().enumerate()
+            .map(|(i, f)| Face { stroke: Stroke::new(colors[i % 2], '#'), ..*f })
+            .collect();
+        Mesh::with_normals(mesh.vertexes.clone(), mesh.normals.clone(), faces).with_uvs(mesh.uvs.clone())
+    }
+
+    fn close(a: Vector, b: Vector) -> bool {
+        (a - b).len() < 1e-5
+    }
+
+    #[test]
+    fn obj_export_loads_back() {
+        let meshes = [
+            two_tone(Mesh::cuboid(1.0, 2.0, 3.0) + Vector::new(5.0, 0.0, 0.0), [[255, 0, 0], [0, 128, 255]]),
+            two_tone(Mesh::uv_sphere(2.0, 8, 4), [[255, 0, 0], [10, 200, 30]]),
+        ];
+        let path = scratch_dir("obj").join("scene.obj");
+        write_obj(&meshes, &path).unwrap();
+        let loaded = parse_mesh(&path).unwrap();
+
+        let originals: Vec<(&Mesh, &Face)> = meshes.iter().flat_map(|m| m.faces.iter().map(move |f| (m, f))).collect();
+        assert_eq!(loaded.vertexes.len(), meshes.iter().map(|m| m.vertexes.len()).sum::<usize>());
+        assert_eq!(loaded.faces.len(), originals.len());
+        for (face, (mesh, original)) in loaded.faces.iter().zip(originals) {
+            for i in 0..3 {
+                assert!(close(loaded.vertexes[face.verts[i]].into(), mesh.vertexes[original.verts[i]].into()));
+                let (normals, original_normals) = (face.normals.unwrap(), original.normals.unwrap());
+                assert!(close(loaded.normals[normals[i]], mesh.normals[original_normals[i]]));
+                let (uvs, original_uvs) = (face.uvs.unwrap(), original.uvs.unwrap());
+                assert_eq!(loaded.uvs[uvs[i]], mesh.uvs[original_uvs[i]]);
+            }
+            assert_eq!(face.stroke.color, original.stroke.color);
+        }
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn stl_export_has_one_record_per_face() {
+        let meshes = [Mesh::cuboid(2.0, 2.0, 2.0), Mesh::icosphere(1.0, 1)];
+        let count = 12 + 80;
+        let path = scratch_dir("stl").join("scene.stl");
+        write_stl(&meshes, &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(bytes.len(), 80 + 4 + 50 * count);
+        assert_eq!(u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize, count);
+        let float = |at: usize| f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        for (record, tri) in meshes.iter().flat_map(|m| m.triangles()).enumerate() {
+            let at = 84 + 50 * record;
+            let normal = Vector::new(float(at), float(at + 4), float(at + 8));
+            assert!((normal.len() - 1.0).abs() < 1e-5);
+            assert!(close(normal, tri.normal()));
+            let first = Vector::new(float(at + 12), float(at + 16), float(at + 20));
+            assert!(close(first, tri.verts[0].into()));
+        }
+    }
+}
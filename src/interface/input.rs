@@ -1,18 +1,75 @@
 use std::collections::VecDeque;
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::time::Duration;
 use regex::Regex;
 use termion::event::Key;
 use crate::debug::debug_logger::{log, log_disp};
 use crate::math::euler_rotation::EulerRotation;
+use crate::math::mesh::{Mesh, NormalWeighting};
 use crate::math::vector::Vector;
+use crate::interface::exporter::ExportFormat;
 use crate::interface::input_context::InputContext;
+use crate::interface::params::Params;
+use crate::interface::parser::parse_mesh;
 use crate::math::quaternion::Quaternion;
+use crate::math::rotation_order::RotationOrder;
+use crate::rendering::object::Object;
+use crate::rendering::background::Background;
+use crate::rendering::axis_gizmo::AxisGizmo;
+use crate::rendering::blinn_phong_shader::BlinnPhongShader;
+use crate::rendering::color_lut::ColorLut;
+use crate::rendering::debug_view::DebugView;
+use crate::rendering::depth_shader::DepthShader;
+use crate::rendering::edge_detect::EdgeDetect;
+use crate::rendering::exposure::Exposure;
+use crate::rendering::directional_light::DirectionalLight;
+use crate::rendering::fill_mode::FillMode;
+use crate::rendering::flat_shader::FlatShader;
+use crate::rendering::grid::Grid;
+use crate::rendering::fog::{Fog, FogMode};
+use crate::rendering::lambert_shader::LambertShader;
+use crate::rendering::line_style::LineStyle;
+use crate::rendering::normal_shader::NormalShader;
+use crate::rendering::object_id_shader::ObjectIdShader;
+use crate::rendering::outline::Outline;
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::posterize::Posterize;
+use crate::rendering::pulse_shader::PulseShader;
+use crate::rendering::render_mode::RenderMode;
+use crate::rendering::scanlines::Scanlines;
+use crate::rendering::shader::Shader;
+use crate::rendering::shading_mode::ShadingMode;
+use crate::rendering::shadows::Shadows;
+use crate::rendering::sharpen::Sharpen;
+use crate::rendering::skybox::Skybox;
+use crate::rendering::text_style::TextStyle;
+use crate::rendering::texture::Texture;
+use crate::rendering::texture_filter::TextureFilter;
+use crate::rendering::toon_shader::ToonShader;
+use crate::rendering::uv_shader::UvShader;
+use crate::rendering::transparency::Transparency;
+
+pub const DEFAULT_CREASE_ANGLE: f32 = 60.0;
+pub const DEFAULT_FACE_NORMAL_LENGTH: f32 = 2.0;
+// c<m|r><axes><delta>: move or rotate, the axes, then the amount
+const CAMERA_PATTERN: &str = r"^c([mr])([xyz]+)([\d.-]+)$";
+// Each icosphere subdivision quadruples the faces; 6 is already 81920
+const MAX_ICOSPHERE_SUBDIVISIONS: usize = 6;
+const MAX_SEGMENTS: usize = 256;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Frame {
+    World,
+    Local,
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum CommandType {
-    Move { delta: Vector },
-    Rotate { delta: Quaternion },
+    Move { delta: Vector, frame: Frame },
+    Rotate { delta: Quaternion, frame: Frame },
+    Scale { factor: Vector },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -30,6 +87,45 @@ pub struct ActiveCommand {
     pub time_passed: Duration,
 }
 
+#[derive(Debug, Clone)]
+pub enum Command {
+    Transform(ActiveCommand),
+    Export { format: ExportFormat, path: PathBuf, obj_id: Option<usize> },
+    Shade(ShadingMode),
+    Mode(RenderMode),
+    Lines(LineStyle),
+    Outline(Option<Outline>),
+    Highlight(bool),
+    Texture(Option<Rc<Texture>>),
+    Filter(TextureFilter),
+    Antialias { factor: u16, coverage_glyphs: bool },
+    Fog(Option<Fog>),
+    Lights(Vec<DirectionalLight>),
+    AddLight(DirectionalLight),
+    Shadows(Option<Shadows>),
+    Opacity(f32),
+    Transparency(Transparency),
+    Background(Option<Background>),
+    Fill(FillMode),
+    Style(TextStyle),
+    // Appends a pass, or clears them all
+    Post(Option<Rc<dyn PostProcess>>),
+    // For the selected object, None going back to the default
+    Shader(Option<Rc<dyn Shader>>),
+    DebugView(Option<DebugView>),
+    FaceNormals(Option<f32>),
+    Grid(Option<Grid>),
+    AxisGizmo(Option<AxisGizmo>),
+    Normals { weighting: NormalWeighting, crease_angle: f32 },
+    Spawn { mesh: Mesh, position: Vector, scale: f32, rotation: Quaternion },
+    Select(usize),
+    Parent { obj_id: usize, parent: Option<usize> },
+    Query(Option<usize>),
+    Pivot(Vector),
+    CameraMove(Vector),
+    CameraRotate(Vector),
+}
+
 #[derive(Debug)]
 pub struct Input {
     command: String,
@@ -49,7 +145,7 @@ impl Input {
         if let Key::Char(c) = k {
             if c == '\n' {
                 match self.parse_command() {
-                    Ok(command) => ctx.buffer.add_command_to_obj(command, 0),
+                    Ok(command) => Self::execute(command, ctx),
                     Err(e) => log(0, e.to_string()),
                 }
             }
@@ -62,6 +158,17 @@ impl Input {
                 *ctx.exit = true
             }
         }
+        // F2 steps through the debug views, F3 toggles the face normal overlay
+        else if matches!(k, Key::F(2)) {
+            Self::execute(Command::DebugView(DebugView::next(ctx.settings.debug_view)), ctx);
+        }
+        else if matches!(k, Key::F(3)) {
+            let length = match ctx.settings.face_normals {
+                Some(_) => None,
+                None => Some(DEFAULT_FACE_NORMAL_LENGTH),
+            };
+            Self::execute(Command::FaceNormals(length), ctx);
+        }
         else if matches!(k, Key::Backspace) {
             self.command.pop();
         }
@@ -93,15 +200,581 @@ impl Input {
     fn get_history(&self, i: usize) -> String {
         self.history.get(i.max(1) - 1).unwrap().clone()
     }
-    fn parse_command(&mut self) -> Result<ActiveCommand, String> {
-        let err = Err(format!("error parsing command '{}'", self.command.clone()));
-        // G1: movement type
-        // G2: axis/axes
-        // G3: delta
-        // G4: interpolation mode
-        // G5: interpolation parameter
-        let regex = Regex::new(r"^([mr])([xyz]+)([\d.-]+)([cl])?([\d.-]+)?$").unwrap();
-        let caps = match regex.captures(&self.command) {
+    fn execute(command: Command, ctx: &mut InputContext) {
+        match command {
+            Command::Transform(cmd) => ctx.buffer.add_command_to_obj(cmd, ctx.buffer.selected),
+            Command::Export { format, path, obj_id } => {
+                match ctx.buffer.export(format, &path, obj_id) {
+                    Ok(()) => log_disp(0, format!("exported to {}", path.display())),
+                    Err(e) => log(0, e),
+                }
+            }
+            Command::Shade(mode) => ctx.settings.shading = mode,
+            Command::Mode(mode) => ctx.settings.render_mode = mode,
+            Command::Lines(style) => ctx.settings.line_style = style,
+            Command::Outline(outline) => ctx.settings.outline = outline,
+            Command::Highlight(highlight) => ctx.settings.highlight_selected = highlight,
+            Command::Texture(texture) => {
+                if let Some(obj) = ctx.buffer.get_obj_mut(ctx.buffer.selected) {
+                    obj.texture = texture;
+                }
+            }
+            Command::Filter(filter) => ctx.settings.texture_filter = filter,
+            Command::Antialias { factor, coverage_glyphs } => {
+                ctx.settings.supersampling = factor;
+                ctx.settings.coverage_glyphs = coverage_glyphs;
+            }
+            Command::Fog(fog) => ctx.settings.fog = fog,
+            Command::Lights(lights) => ctx.settings.lights = lights,
+            Command::AddLight(light) => ctx.settings.lights.push(light),
+            Command::Shadows(shadows) => ctx.settings.shadows = shadows,
+            Command::Opacity(opacity) => {
+                if let Some(obj) = ctx.buffer.get_obj_mut(ctx.buffer.selected) {
+                    obj.opacity = opacity;
+                }
+            }
+            Command::Transparency(transparency) => ctx.settings.transparency = transparency,
+            Command::Background(background) => ctx.settings.background = background,
+            Command::Fill(fill) => ctx.settings.fill = fill,
+            Command::Post(Some(pass)) => ctx.settings.post.push(pass),
+            Command::Post(None) => ctx.settings.post.clear(),
+            Command::Shader(shader) => {
+                if let Some(obj) = ctx.buffer.get_obj_mut(ctx.buffer.selected) {
+                    obj.shader = shader;
+                }
+            }
+            Command::DebugView(view) => ctx.settings.debug_view = view,
+            Command::FaceNormals(length) => ctx.settings.face_normals = length,
+            Command::Grid(grid) => ctx.settings.grid = grid,
+            Command::AxisGizmo(gizmo) => ctx.settings.axis_gizmo = gizmo,
+            Command::Style(style) => {
+                if let Some(obj) = ctx.buffer.get_obj_mut(ctx.buffer.selected) {
+                    obj.style = style;
+                }
+            }
+            Command::Normals { weighting, crease_angle } => {
+                if let Some(obj) = ctx.buffer.get_obj_mut(ctx.buffer.selected) {
+                    obj.base_mesh = obj.base_mesh.compute_normals(weighting, crease_angle);
+                }
+            }
+            Command::Spawn { mesh, position, scale, rotation } => {
+                let mut obj = Object::new(mesh);
+                obj.set_position(position);
+                obj.set_rotation(rotation);
+                obj.set_scale(Vector::new(scale, scale, scale));
+                let id = ctx.buffer.add_mesh_worldspace(obj);
+                ctx.buffer.selected = id;
+                log_disp(0, format!("spawned object {}", id));
+            }
+            Command::Select(obj_id) => {
+                if let Err(e) = ctx.buffer.select(obj_id) {
+                    log(0, e);
+                }
+            }
+            Command::Parent { obj_id, parent } => {
+                if let Err(e) = ctx.buffer.set_parent(obj_id, parent) {
+                    log(0, e);
+                }
+            }
+            Command::Query(obj_id) => {
+                let obj_id = obj_id.unwrap_or(ctx.buffer.selected);
+                match ctx.buffer.local_and_world(obj_id) {
+                    Ok((local, world)) => log_disp(0, format!("object {}: local {} | world {}", obj_id, local, world)),
+                    Err(e) => log(0, e),
+                }
+            }
+            // Camera moves are relative to where it's facing
+            Command::CameraMove(delta) => ctx.camera.mv(ctx.camera.rotation * delta),
+            Command::CameraRotate(euler) => ctx.camera.rotate(euler),
+            Command::Pivot(pivot) => {
+                if let Some(obj) = ctx.buffer.get_obj_mut(ctx.buffer.selected) {
+                    obj.set_pivot(pivot);
+                }
+            }
+        }
+    }
+    fn parse_command(&mut self) -> Result<Command, String> {
+        let line = self.command.trim();
+        let cmd = match line.split_whitespace().next() {
+            Some("export") => Self::parse_export(line)?,
+            Some("shade") => Self::parse_shade(line)?,
+            Some("mode") | Some("lines") => Self::parse_mode(line)?,
+            Some("outline") => Self::parse_outline(line)?,
+            Some("highlight") => Self::parse_highlight(line)?,
+            Some("texture") | Some("filter") => Self::parse_texture(line)?,
+            Some("aa") => Self::parse_antialias(line)?,
+            Some("fog") => Self::parse_fog(line)?,
+            Some("light") => Self::parse_light(line)?,
+            Some("shadows") => Self::parse_shadows(line)?,
+            Some("opacity") | Some("transparency") => Self::parse_opacity(line)?,
+            Some("background") => Self::parse_background(line)?,
+            Some("fill") | Some("style") => Self::parse_fill(line)?,
+            Some("post") => Self::parse_post(line)?,
+            Some("shader") => Self::parse_shader(line)?,
+            Some("debug") => Self::parse_debug(line)?,
+            Some("grid") | Some("gizmo") => Self::parse_grid(line)?,
+            Some("normals") => Self::parse_normals(line)?,
+            Some("spawn") => Self::parse_spawn(line)?,
+            Some("load") => Self::parse_load(line)?,
+            Some("select") | Some("parent") | Some("unparent") | Some("transform") => Self::parse_scene(line)?,
+            Some("pivot") => Self::parse_pivot(line)?,
+            Some(_) if Regex::new(CAMERA_PATTERN).unwrap().is_match(line) => Self::parse_camera(line)?,
+            Some(token) if token.starts_with(['m', 'r', 's']) => Command::Transform(Self::parse_transform(line)?),
+            _ => return Err(format!("unknown command '{}'", line)),
+        };
+
+        self.add_history(self.command.clone());
+        self.command.clear();
+
+        Ok(cmd)
+    }
+    // export <obj|stl> <path> [object id]
+    fn parse_export(line: &str) -> Result<Command, String> {
+        let err = Err(format!("error parsing command '{}'", line));
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+
+        let format = match tokens.first() {
+            Some(&"obj") => ExportFormat::Obj,
+            Some(&"stl") => ExportFormat::Stl,
+            _ => return err
+        };
+        let path = match tokens.get(1) {
+            Some(path) => PathBuf::from(path),
+            None => return err
+        };
+        let obj_id = match tokens.get(2) {
+            Some(id) => match id.parse::<usize>() {
+                Ok(id) => Some(id),
+                Err(_) => return err
+            },
+            None => None
+        };
+        if tokens.len() > 3 {
+            return err
+        }
+
+        Ok(Command::Export { format, path, obj_id })
+    }
+    // shade <flat|gouraud|phong>
+    fn parse_shade(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+        match tokens.as_slice() {
+            ["flat"] => Ok(Command::Shade(ShadingMode::Flat)),
+            ["gouraud"] => Ok(Command::Shade(ShadingMode::Gouraud)),
+            ["phong"] => Ok(Command::Shade(ShadingMode::Phong)),
+            _ => Err(format!("error parsing command '{}'", line))
+        }
+    }
+    // mode <solid|wire|overlay|hidden> | lines <slope|braille>
+    fn parse_mode(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["mode", "solid"] => Ok(Command::Mode(RenderMode::Solid)),
+            ["mode", "wire"] => Ok(Command::Mode(RenderMode::Wireframe)),
+            ["mode", "overlay"] => Ok(Command::Mode(RenderMode::WireframeOverSolid)),
+            ["mode", "hidden"] => Ok(Command::Mode(RenderMode::HiddenLine)),
+            ["lines", "slope"] => Ok(Command::Lines(LineStyle::Slope)),
+            ["lines", "braille"] => Ok(Command::Lines(LineStyle::Braille)),
+            _ => Err(format!("error parsing command '{}'", line))
+        }
+    }
+    // outline off | outline [color=r,g,b] [crease=degrees]
+    fn parse_outline(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+        if tokens == ["off"] {
+            return Ok(Command::Outline(None));
+        }
+        let mut params = Params::parse(tokens.into_iter())?;
+        let color = params.get_vector("color", Vector::new(255.0, 255.0, 255.0))?;
+        let crease_angle: f32 = params.get("crease", DEFAULT_CREASE_ANGLE)?;
+        params.finish()?;
+
+        let color = [color.x, color.y, color.z].map(|c| c.clamp(0.0, 255.0) as u8);
+        Ok(Command::Outline(Some(Outline::new(color, crease_angle.to_radians()))))
+    }
+    // highlight on | highlight off, for the outline around the selected object
+    fn parse_highlight(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["highlight", "on"] => Ok(Command::Highlight(true)),
+            ["highlight", "off"] => Ok(Command::Highlight(false)),
+            _ => Err(format!("error parsing command '{}'", line))
+        }
+    }
+    // texture <path.ppm|path.pgm> | texture off | filter <nearest|bilinear>
+    fn parse_texture(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["texture", "off"] => Ok(Command::Texture(None)),
+            ["texture", path] => Ok(Command::Texture(Some(Rc::new(Texture::load(Path::new(path))?)))),
+            ["filter", "nearest"] => Ok(Command::Filter(TextureFilter::Nearest)),
+            ["filter", "bilinear"] => Ok(Command::Filter(TextureFilter::Bilinear)),
+            _ => Err(format!("error parsing command '{}'", line))
+        }
+    }
+    // aa off | aa <samples per axis, up to 8> [glyphs]
+    fn parse_antialias(line: &str) -> Result<Command, String> {
+        let err = Err(format!("error parsing command '{}'", line));
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+        let (factor, coverage_glyphs) = match tokens.as_slice() {
+            ["off"] => (Ok(1), false),
+            [factor] => (factor.parse::<u16>(), false),
+            [factor, "glyphs"] => (factor.parse::<u16>(), true),
+            _ => return err
+        };
+        match factor {
+            Ok(factor) if (1..=8).contains(&factor) => Ok(Command::Antialias { factor, coverage_glyphs }),
+            _ => err
+        }
+    }
+    // fog off | fog linear [start=d] [end=d] | fog <exp|exp2> [density=d], then [color=r,g,b] [thin=true].
+    // Without a colour the fog fades toward the background.
+    fn parse_fog(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let kind = match tokens.next() {
+            Some("off") => return Ok(Command::Fog(None)),
+            Some(kind) => kind,
+            None => return Err(format!("error parsing command '{}'", line)),
+        };
+        let mut params = Params::parse(tokens)?;
+        let mode = match kind {
+            "linear" => FogMode::Linear { start: params.get("start", 40.0)?, end: params.get("end", 120.0)? },
+            "exp" => FogMode::Exp { density: params.get("density", 0.02)? },
+            "exp2" => FogMode::Exp2 { density: params.get("density", 0.015)? },
+            _ => return Err(format!("unknown fog mode '{}'", kind)),
+        };
+        let color = params.get_optional_vector("color")?.map(|c| [c.x, c.y, c.z].map(|c| c.clamp(0.0, 255.0) as u8));
+        let thin_glyphs = params.get("thin", false)?;
+        params.finish()?;
+
+        Ok(Command::Fog(Some(Fog::new(mode, color, thin_glyphs))))
+    }
+    // light clear | light <add|set> dir=x,y,z [shadows=false]
+    fn parse_light(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let action = match tokens.next() {
+            Some("clear") if tokens.next().is_none() => return Ok(Command::Lights(Vec::new())),
+            Some(action @ ("add" | "set")) => action,
+            _ => return Err(format!("error parsing command '{}'", line)),
+        };
+        let mut params = Params::parse(tokens)?;
+        let direction = params.get_vector("dir", Vector::new(0.0, -1.0, 0.0))?;
+        let casts_shadows = params.get("shadows", true)?;
+        params.finish()?;
+
+        if direction.len() == 0.0 {
+            return Err("light direction can't be zero".to_string());
+        }
+        let light = DirectionalLight::new(direction, casts_shadows);
+        match action {
+            "add" => Ok(Command::AddLight(light)),
+            _ => Ok(Command::Lights(vec![light])),
+        }
+    }
+    // shadows off | shadows [res=texels] [bias=d] [pcf=radius] [strength=0..1]
+    fn parse_shadows(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+        if tokens == ["off"] {
+            return Ok(Command::Shadows(None));
+        }
+        let mut params = Params::parse(tokens.into_iter())?;
+        let resolution: u16 = params.get("res", 256)?;
+        let bias = params.get("bias", 0.2)?;
+        let pcf = params.get("pcf", 1)?;
+        let strength: f32 = params.get("strength", 0.6)?;
+        params.finish()?;
+
+        if !(16..=2048).contains(&resolution) {
+            return Err("shadow resolution must be between 16 and 2048".to_string());
+        }
+        Ok(Command::Shadows(Some(Shadows::new(resolution, bias, pcf.min(4), strength.clamp(0.0, 1.0)))))
+    }
+    // opacity <0..1> | transparency <sorted|weighted>
+    fn parse_opacity(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["opacity", opacity] => match opacity.parse::<f32>() {
+                Ok(opacity) if (0.0..=1.0).contains(&opacity) => Ok(Command::Opacity(opacity)),
+                _ => Err(format!("opacity must be between 0 and 1, got '{}'", opacity)),
+            },
+            ["transparency", "sorted"] => Ok(Command::Transparency(Transparency::Sorted)),
+            ["transparency", "weighted"] => Ok(Command::Transparency(Transparency::WeightedBlended)),
+            _ => Err(format!("error parsing command '{}'", line))
+        }
+    }
+    // background off | background solid [color=r,g,b] | background gradient [horizon=r,g,b] [zenith=r,g,b]
+    // | background skybox <+x> <-x> <+y> <-y> <+z> <-z>
+    fn parse_background(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let kind = match tokens.next() {
+            Some("off") => return Ok(Command::Background(None)),
+            Some(kind) => kind,
+            None => return Err(format!("error parsing command '{}'", line)),
+        };
+        if kind == "skybox" {
+            let paths: Vec<&Path> = tokens.map(Path::new).collect();
+            let Ok(paths) = <[&Path; 6]>::try_from(paths) else {
+                return Err("a skybox needs six images: +x -x +y -y +z -z".to_string());
+            };
+            return Ok(Command::Background(Some(Background::Skybox(Rc::new(Skybox::load(paths)?)))));
+        }
+
+        let mut params = Params::parse(tokens)?;
+        let to_color = |v: Vector| [v.x, v.y, v.z].map(|c| c.clamp(0.0, 255.0) as u8);
+        let background = match kind {
+            "solid" => Background::Solid(to_color(params.get_vector("color", Vector::new(20.0, 20.0, 30.0))?)),
+            "gradient" => Background::Gradient {
+                horizon: to_color(params.get_vector("horizon", Vector::new(180.0, 200.0, 220.0))?),
+                zenith: to_color(params.get_vector("zenith", Vector::new(40.0, 80.0, 160.0))?),
+            },
+            _ => return Err(format!("unknown background '{}'", kind)),
+        };
+        params.finish()?;
+        Ok(Command::Background(Some(background)))
+    }
+    // fill <glyph|block> | style <none|bold,dim,reverse>
+    fn parse_fill(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["fill", "glyph"] => Ok(Command::Fill(FillMode::Glyph)),
+            ["fill", "block"] => Ok(Command::Fill(FillMode::Block)),
+            ["style", style] => Ok(Command::Style(style.parse()?)),
+            _ => Err(format!("error parsing command '{}'", line))
+        }
+    }
+    // post off | post edge [threshold=f] [color=r,g,b] | post posterize [levels=n] [cel=true]
+    // | post exposure [ev=f] [gamma=f] | post sharpen [amount=f] | post scanlines [strength=f] | post lut <path.cube>
+    fn parse_post(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let kind = match tokens.next() {
+            Some("off") => return Ok(Command::Post(None)),
+            Some(kind) => kind,
+            None => return Err(format!("error parsing command '{}'", line)),
+        };
+        if kind == "lut" {
+            return match (tokens.next(), tokens.next()) {
+                (Some(path), None) => Ok(Command::Post(Some(Rc::new(ColorLut::load(Path::new(path))?)))),
+                _ => Err(format!("error parsing command '{}'", line)),
+            };
+        }
+
+        let mut params = Params::parse(tokens)?;
+        let pass: Rc<dyn PostProcess> = match kind {
+            "edge" => {
+                let color = params.get_vector("color", Vector::zero())?;
+                let color = [color.x, color.y, color.z].map(|c| c.clamp(0.0, 255.0) as u8);
+                Rc::new(EdgeDetect::new(params.get("threshold", 0.1)?, color))
+            }
+            "posterize" => Rc::new(Posterize::new(params.get("levels", 4)?, params.get("cel", false)?)),
+            "exposure" => Rc::new(Exposure::new(params.get("ev", 0.0)?, params.get("gamma", 1.0)?)),
+            "sharpen" => Rc::new(Sharpen::new(params.get("amount", 0.5)?)),
+            "scanlines" => Rc::new(Scanlines::new(params.get("strength", 0.3)?)),
+            _ => return Err(format!("unknown post process '{}'", kind)),
+        };
+        params.finish()?;
+        Ok(Command::Post(Some(pass)))
+    }
+    // shader default | shader flat | shader lambert [half=true] | shader blinn [ambient=f] [specular=f] [shininess=f]
+    // | shader toon [bands=n] | shader normal | shader depth [max=d] | shader uv | shader id
+    // | shader pulse [color=r,g,b] [period=seconds]
+    fn parse_shader(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let kind = match tokens.next() {
+            Some("default") => return Ok(Command::Shader(None)),
+            Some(kind) => kind,
+            None => return Err(format!("error parsing command '{}'", line)),
+        };
+        let mut params = Params::parse(tokens)?;
+        let shader: Rc<dyn Shader> = match kind {
+            "flat" => Rc::new(FlatShader),
+            "lambert" => Rc::new(LambertShader::new(params.get("half", true)?)),
+            "blinn" => Rc::new(BlinnPhongShader::new(params.get("ambient", 0.2)?, params.get("specular", 0.5)?, params.get("shininess", 32.0)?)),
+            "toon" => Rc::new(ToonShader::new(params.get("bands", 3)?)),
+            "normal" => Rc::new(NormalShader),
+            "depth" => Rc::new(DepthShader::new(params.get("max", 100.0)?)),
+            "uv" => Rc::new(UvShader),
+            "id" => Rc::new(ObjectIdShader),
+            "pulse" => {
+                let color = params.get_vector("color", Vector::new(255.0, 200.0, 0.0))?;
+                let color = [color.x, color.y, color.z].map(|c| c.clamp(0.0, 255.0) as u8);
+                Rc::new(PulseShader::new(color, params.get("period", 1.0)?))
+            }
+            _ => return Err(format!("unknown shader '{}'", kind)),
+        };
+        params.finish()?;
+        Ok(Command::Shader(Some(shader)))
+    }
+    // debug off | debug normals | debug depth | debug overdraw | debug id
+    // | debug facenormals off | debug facenormals [len=d]
+    fn parse_debug(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let view = match tokens.next() {
+            Some("facenormals") => {
+                let tokens: Vec<&str> = tokens.collect();
+                if tokens == ["off"] {
+                    return Ok(Command::FaceNormals(None));
+                }
+                let mut params = Params::parse(tokens.into_iter())?;
+                let length: f32 = params.get("len", DEFAULT_FACE_NORMAL_LENGTH)?;
+                params.finish()?;
+                if length <= 0.0 {
+                    return Err(format!("face normal length must be positive, got {}", length));
+                }
+                return Ok(Command::FaceNormals(Some(length)));
+            }
+            Some("off") => None,
+            Some("normals") => Some(DebugView::Normals),
+            Some("depth") => Some(DebugView::Depth),
+            Some("overdraw") => Some(DebugView::Overdraw),
+            Some("id") => Some(DebugView::ObjectId),
+            _ => return Err(format!("error parsing command '{}'", line)),
+        };
+        match tokens.next() {
+            None => Ok(Command::DebugView(view)),
+            Some(_) => Err(format!("error parsing command '{}'", line)),
+        }
+    }
+    // grid off | grid [spacing=d] [extent=lines] [color=r,g,b] | gizmo off | gizmo [size=cells]
+    fn parse_grid(line: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["grid", "off"] => return Ok(Command::Grid(None)),
+            ["gizmo", "off"] => return Ok(Command::AxisGizmo(None)),
+            _ => {}
+        }
+        let mut params = Params::parse(tokens.iter().skip(1).copied())?;
+        let command = match tokens.first() {
+            Some(&"grid") => {
+                let spacing: f32 = params.get("spacing", 5.0)?;
+                let extent = params.get("extent", 10)?;
+                let color = params.get_vector("color", Vector::new(90.0, 90.0, 90.0))?;
+                if spacing <= 0.0 {
+                    return Err(format!("grid spacing must be positive, got {}", spacing));
+                }
+                let color = [color.x, color.y, color.z].map(|c| c.clamp(0.0, 255.0) as u8);
+                Command::Grid(Some(Grid::new(spacing, extent, color)))
+            }
+            Some(&"gizmo") => Command::AxisGizmo(Some(AxisGizmo::new(params.get("size", 4)?))),
+            _ => return Err(format!("error parsing command '{}'", line)),
+        };
+        params.finish()?;
+        Ok(command)
+    }
+    // normals <area|angle> [crease angle in degrees]
+    fn parse_normals(line: &str) -> Result<Command, String> {
+        let err = Err(format!("error parsing command '{}'", line));
+        let tokens: Vec<&str> = line.split_whitespace().skip(1).collect();
+
+        let weighting = match tokens.first() {
+            Some(&"area") => NormalWeighting::Area,
+            Some(&"angle") => NormalWeighting::Angle,
+            _ => return err
+        };
+        let crease_angle = match tokens.get(1) {
+            Some(angle) => match angle.parse::<f32>() {
+                Ok(angle) => angle.to_radians(),
+                Err(_) => return err
+            },
+            None => DEFAULT_CREASE_ANGLE.to_radians()
+        };
+        if tokens.len() > 2 {
+            return err
+        }
+
+        Ok(Command::Normals { weighting, crease_angle })
+    }
+    // spawn <sphere|icosphere|box|cylinder|cone|plane|torus> [key=value ...] [pos=x,y,z]
+    fn parse_spawn(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let primitive = match tokens.next() {
+            Some(primitive) => primitive,
+            None => return Err(format!("error parsing command '{}'", line)),
+        };
+        let mut params = Params::parse(tokens)?;
+
+        let mesh = match primitive {
+            "sphere" => Mesh::uv_sphere(params.get_dimension("r", 10.0)?, params.get_count("seg", 24, MAX_SEGMENTS)?, params.get_count("rings", 12, MAX_SEGMENTS)?),
+            "icosphere" => Mesh::icosphere(params.get_dimension("r", 10.0)?, params.get_count("sub", 2, MAX_ICOSPHERE_SUBDIVISIONS)?),
+            "box" => {
+                let size = params.get_dimension("size", 10.0)?;
+                Mesh::cuboid(params.get_dimension("w", size)?, params.get_dimension("h", size)?, params.get_dimension("d", size)?)
+            }
+            "cylinder" => Mesh::cylinder(params.get_dimension("r", 5.0)?, params.get_dimension("h", 10.0)?, params.get_count("seg", 16, MAX_SEGMENTS)?),
+            "cone" => Mesh::cone(params.get_dimension("r", 5.0)?, params.get_dimension("h", 10.0)?, params.get_count("seg", 16, MAX_SEGMENTS)?),
+            "plane" => {
+                let size = params.get_dimension("size", 20.0)?;
+                Mesh::plane(params.get_dimension("w", size)?, params.get_dimension("d", size)?, params.get_count("sub", 0, MAX_SEGMENTS)?)
+            }
+            "torus" => Mesh::torus(params.get_dimension("R", 10.0)?, params.get_dimension("r", 3.0)?, params.get_count("seg", 24, MAX_SEGMENTS)?, params.get_count("sides", 12, MAX_SEGMENTS)?),
+            _ => return Err(format!("unknown primitive '{}'", primitive)),
+        };
+        Self::placed(mesh, params)
+    }
+    // load <path> [pos=x,y,z] [scale=s] [rot=x,y,z] [order=xyz]
+    fn parse_load(line: &str) -> Result<Command, String> {
+        let mut tokens = line.split_whitespace().skip(1);
+        let path = match tokens.next() {
+            Some(path) => PathBuf::from(path),
+            None => return Err(format!("error parsing command '{}'", line)),
+        };
+        let params = Params::parse(tokens)?;
+
+        let mesh = parse_mesh(&path)?;
+        let mesh = if mesh.normals.is_empty() {
+            mesh.compute_normals(NormalWeighting::Angle, DEFAULT_CREASE_ANGLE.to_radians())
+        } else {
+            mesh
+        };
+        Self::placed(mesh, params)
+    }
+    // Where a spawned or loaded mesh starts out. rot is in degrees, applied in the given axis order.
+    fn placed(mesh: Mesh, mut params: Params) -> Result<Command, String> {
+        let position = params.get_vector("pos", Vector::zero())?;
+        let scale = params.get("scale", 1.0)?;
+        let angles = params.get_vector("rot", Vector::zero())?;
+        let order = params.get("order", RotationOrder::default())?;
+        params.finish()?;
+
+        let rotation = EulerRotation::from_degrees(angles.x, angles.y, angles.z).with_order(order).into();
+        Ok(Command::Spawn { mesh, position, scale, rotation })
+    }
+    // select <id> | parent <id> <parent id> | unparent <id> | transform [id]
+    fn parse_scene(line: &str) -> Result<Command, String> {
+        let err = Err(format!("error parsing command '{}'", line));
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let mut ids = Vec::with_capacity(2);
+        for token in &tokens[1..] {
+            match token.parse::<usize>() {
+                Ok(id) => ids.push(id),
+                Err(_) => return err
+            }
+        }
+
+        match (tokens[0], ids.as_slice()) {
+            ("select", [id]) => Ok(Command::Select(*id)),
+            ("parent", [id, parent]) => Ok(Command::Parent { obj_id: *id, parent: Some(*parent) }),
+            ("unparent", [id]) => Ok(Command::Parent { obj_id: *id, parent: None }),
+            ("transform", []) => Ok(Command::Query(None)),
+            ("transform", [id]) => Ok(Command::Query(Some(*id))),
+            _ => err
+        }
+    }
+    // pivot <x> <y> <z>, in the object's mesh space
+    fn parse_pivot(line: &str) -> Result<Command, String> {
+        let components: Vec<f32> = match line.split_whitespace().skip(1).map(|t| t.parse::<f32>()).collect() {
+            Ok(components) => components,
+            Err(_) => return Err(format!("error parsing command '{}'", line)),
+        };
+        match components.as_slice() {
+            [x, y, z] => Ok(Command::Pivot(Vector::new(*x, *y, *z))),
+            _ => Err(format!("error parsing command '{}'", line)),
+        }
+    }
+    // c<m|r><axes><delta>, e.g. `cmz10` or `cry-15`. The camera only moves instantly.
+    fn parse_camera(line: &str) -> Result<Command, String> {
+        let err = Err(format!("error parsing command '{}'", line));
+        let regex = Regex::new(CAMERA_PATTERN).unwrap();
+        let caps = match regex.captures(line) {
             Some(caps) => caps,
             None => return err
         };
@@ -115,20 +788,67 @@ impl Input {
                 _ => return err
             }
         }
+        let delta = match caps[3].parse::<f32>() {
+            Ok(val) => val,
+            Err(_) => return err
+        };
+
+        match &caps[1] {
+            "m" => Ok(Command::CameraMove(vec * delta)),
+            "r" => Ok(Command::CameraRotate(vec * delta.to_radians())),
+            _ => err
+        }
+    }
+    fn parse_transform(line: &str) -> Result<ActiveCommand, String> {
+        let err = Err(format!("error parsing command '{}'", line));
+        // G1: movement type
+        // G2: frame, w for world or l for local
+        // G3: axis/axes, or an arbitrary axis as <x,y,z>
+        // G4: delta
+        // G5: interpolation mode
+        // G6: interpolation parameter
+        let regex = Regex::new(r"^([mrs])([wl])?([xyz]+|<[\d.,-]+>)([\d.-]+)([cl])?([\d.-]+)?$").unwrap();
+        let caps = match regex.captures(line) {
+            Some(caps) => caps,
+            None => return err
+        };
 
-        let scale = match caps[3].parse::<f32>() {
+        let mut vec = Vector::zero();
+        let axis_angle = caps[3].starts_with('<');
+        if axis_angle {
+            let components: Vec<f32> = match caps[3].trim_matches(['<', '>']).split(',').map(|c| c.parse::<f32>()).collect() {
+                Ok(components) => components,
+                Err(_) => return err
+            };
+            vec = match components.as_slice() {
+                [x, y, z] if *x != 0.0 || *y != 0.0 || *z != 0.0 => Vector::new(*x, *y, *z).normalized(),
+                _ => return err
+            };
+        }
+        else {
+            for c in caps[3].chars() {
+                match c {
+                    'x' => vec.x = 1.0,
+                    'y' => vec.y = 1.0,
+                    'z' => vec.z = 1.0,
+                    _ => return err
+                }
+            }
+        }
+
+        let scale = match caps[4].parse::<f32>() {
             Ok(val) => val,
             Err(_) => return err
         };
 
-        let dur = match caps.get(5) {
+        let dur = match caps.get(6) {
             Some(s) => Duration::from_secs_f32( match s.as_str().parse::<f32>() {
                 Ok(val) => val,
                 Err(_) => return err
             }),
             None => Duration::from_secs(0)
         };
-        let interpolation = match caps.get(4) {
+        let interpolation = match caps.get(5) {
             Some(i) => match i.as_str() {
                 "l" => InterpolationMode::Linear { duration: dur },
                 "c" => InterpolationMode::Continuous,
@@ -137,15 +857,33 @@ impl Input {
             None => InterpolationMode::Instant
         };
 
-        let cmd = match &caps[1] {
-            "m" => CommandType::Move { delta: vec * scale },
-            "r" => CommandType::Rotate { delta: Quaternion::from_euler_vec(vec * scale.to_radians()) },
+        // Moves default to the world frame and rotations to the object's own axes
+        let frame = caps.get(2).map(|f| f.as_str());
+        let cmd = match (&caps[1], frame) {
+            ("m", _) => CommandType::Move {
+                delta: vec * scale,
+                frame: if frame == Some("l") { Frame::Local } else { Frame::World },
+            },
+            ("r", _) => CommandType::Rotate {
+                delta: if axis_angle {
+                    Quaternion::from_axis_angle(vec, scale.to_radians())
+                } else {
+                    // The axes are applied in the order they're written, so rzx45 turns about z first
+                    let order = match RotationOrder::from_letters(&caps[3]) {
+                        Some(order) => order,
+                        None => return err
+                    };
+                    Quaternion::from_euler_vec(vec * scale.to_radians(), order)
+                },
+                frame: if frame == Some("w") { Frame::World } else { Frame::Local },
+            },
+            // Axes that aren't named keep their size. Scaling always happens along the object's axes.
+            ("s", None | Some("l")) if scale > 0.0 && !axis_angle => CommandType::Scale {
+                factor: Vector::new(1.0, 1.0, 1.0) + vec * (scale - 1.0)
+            },
             _ => return err
         };
 
-        self.add_history(self.command.clone());
-        self.command.clear();
-
         Ok(
             ActiveCommand {
                 command: cmd,
@@ -160,4 +898,72 @@ impl Display for Input {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.command.as_str())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::math::vector::Vector;
+    use super::{Command, CommandType, Frame, Input};
+
+    fn parse(line: &str) -> Result<Command, String> {
+        let mut input = Input::new();
+        input.command = line.to_string();
+        input.parse_command()
+    }
+
+    #[test]
+    fn camera_commands_need_the_full_syntax() {
+        assert!(matches!(parse("cmz10"), Ok(Command::CameraMove(Vector { z: 10.0, .. }))));
+        assert!(matches!(parse("cry-15"), Ok(Command::CameraRotate(v)) if (v.y + 15.0_f32.to_radians()).abs() < 1e-6));
+        for line in ["clear", "cull", "cm", "camera"] {
+            assert_eq!(parse(line).err(), Some(format!("unknown command '{}'", line)));
+        }
+    }
+
+    #[test]
+    fn load_places_a_mesh_without_its_material_library() {
+        let dir = std::env::temp_dir().join(format!("term3d_load_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("triangle.obj");
+        fs::write(&path, "mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n").unwrap();
+
+        let command = parse(&format!("load {} pos=1,2,3 scale=2", path.display()));
+        fs::remove_dir_all(&dir).unwrap();
+        match command {
+            Ok(Command::Spawn { mesh, position, scale, .. }) => {
+                assert_eq!(mesh.faces.len(), 1);
+                // Loaded without its materials, so the face falls back to white
+                assert_eq!(mesh.faces[0].stroke.color, [255, 255, 255]);
+                // Normals are computed when the file has none
+                assert_eq!(mesh.normals.len(), 3);
+                assert!((position - Vector::new(1.0, 2.0, 3.0)).len() < 1e-6);
+                assert_eq!(scale, 2.0);
+            }
+            Ok(_) => panic!("load didn't spawn a mesh"),
+            Err(e) => panic!("{}", e),
+        }
+        assert!(parse("load /nonexistent/term3d.obj").is_err());
+        assert!(parse("load").is_err());
+    }
+
+    fn frame(line: &str) -> Option<Frame> {
+        match parse(line) {
+            Ok(Command::Transform(cmd)) => match cmd.command {
+                CommandType::Move { frame, .. } | CommandType::Rotate { frame, .. } => Some(frame),
+                CommandType::Scale { .. } => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn transform_frames_default_by_kind() {
+        assert!(matches!(frame("mx1"), Some(Frame::World)));
+        assert!(matches!(frame("mlx1"), Some(Frame::Local)));
+        assert!(matches!(frame("rx90"), Some(Frame::Local)));
+        assert!(matches!(frame("rwx90"), Some(Frame::World)));
+        assert!(matches!(frame("rw<1,1,0>45l2"), Some(Frame::World)));
+        assert!(parse("rqx90").is_err());
+    }
+}
@@ -3,10 +3,12 @@ use crate::math::projection_type::ProjectionType;
 use crate::rendering::camera::Camera;
 use crate::rendering::object::Object;
 use crate::rendering::render_buffer::RenderBuffer;
+use crate::rendering::render_settings::RenderSettings;
 
 pub struct InputContext<'a> {
     pub camera: &'a mut Camera,
     pub buffer: &'a mut RenderBuffer,
     pub projection_type: &'a mut ProjectionType,
+    pub settings: &'a mut RenderSettings,
     pub exit: &'a mut bool,
 }
\ No newline at end of file
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::math::vector::Vector;
+
+// key=value arguments trailing a command, e.g. `spawn sphere r=10 seg=24`
+#[derive(Debug)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    pub fn parse<'a, I: Iterator<Item = &'a str>>(tokens: I) -> Result<Params, String> {
+        let mut values = HashMap::new();
+        for token in tokens {
+            match token.split_once('=') {
+                Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+                    values.insert(key.to_string(), value.to_string());
+                }
+                _ => return Err(format!("expected key=value, found '{}'", token)),
+            }
+        }
+        Ok(Params { values })
+    }
+    pub fn get<T: FromStr>(&mut self, key: &str, default: T) -> Result<T, String> {
+        match self.values.remove(key) {
+            Some(value) => value.parse::<T>().map_err(|_| format!("invalid value for {}: '{}'", key, value)),
+            None => Ok(default),
+        }
+    }
+    // For counts that decide how much gets built, so a typo can't ask for millions of faces
+    pub fn get_count(&mut self, key: &str, default: usize, max: usize) -> Result<usize, String> {
+        let count = self.get(key, default)?;
+        if count > max {
+            return Err(format!("{} can be at most {}, got {}", key, max, count));
+        }
+        Ok(count)
+    }
+    // For sizes, which have to be positive for the shape to make sense
+    pub fn get_dimension(&mut self, key: &str, default: f32) -> Result<f32, String> {
+        let dimension: f32 = self.get(key, default)?;
+        if !dimension.is_finite() || dimension <= 0.0 {
+            return Err(format!("{} must be positive, got {}", key, dimension));
+        }
+        Ok(dimension)
+    }
+    pub fn get_vector(&mut self, key: &str, default: Vector) -> Result<Vector, String> {
+        Ok(self.get_optional_vector(key)?.unwrap_or(default))
+    }
+    // For when leaving the key out means something other than a fixed default
+    pub fn get_optional_vector(&mut self, key: &str) -> Result<Option<Vector>, String> {
+        let value = match self.values.remove(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let components: Vec<f32> = match value.split(',').map(|c| c.parse::<f32>()).collect() {
+            Ok(components) => components,
+            Err(_) => return Err(format!("invalid value for {}: '{}'", key, value)),
+        };
+        match components.as_slice() {
+            [x, y, z] => Ok(Some(Vector::new(*x, *y, *z))),
+            _ => Err(format!("expected x,y,z for {}: '{}'", key, value)),
+        }
+    }
+    // Fails on any key that was never read, so typos don't silently fall back to defaults
+    pub fn finish(self) -> Result<(), String> {
+        match self.values.keys().next() {
+            Some(key) => Err(format!("unknown parameter '{}'", key)),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,10 +1,12 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
 use std::fs::File;
 use std::num::ParseIntError;
 use std::path::Path;
-use crate::math::mesh::Mesh;
-use crate::math::triangle::Triangle;
+use crate::debug::debug_logger::log_disp;
+use crate::math::mesh::{Face, Mesh};
+use crate::math::vector::Vector;
 use crate::math::vertex::Vertex;
 use crate::rendering::stroke::Stroke;
 
@@ -15,7 +17,11 @@ pub fn parse_mesh(path: &Path) -> Result<Mesh, String> {
     };
     
     let mut vertexes: Vec<Vertex> = Vec::new();
-    let mut faces: Vec<Triangle> = Vec::new();
+    let mut normals: Vec<Vector> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut faces: Vec<Face> = Vec::new();
+    let mut materials: HashMap<String, [u8; 3]> = HashMap::new();
+    let mut stroke = Stroke::new([255, 255, 255], '█');
     
     for line in contents.lines() {
         let mut tokens = line.split_whitespace();
@@ -23,30 +29,67 @@ pub fn parse_mesh(path: &Path) -> Result<Mesh, String> {
             Some(token) => 
                 match token {
                     "v" => {
-                        let mut components: Vec<f32> = Vec::new();
-                        for i in 0..3 {
-                            match tokens.next() {
-                                Some(token) => components.push(match token.parse::<f32>() {
-                                    Ok(v) => v,
-                                    Err(e) => return Err(e.to_string()),
-                                }),
-                                None => return Err(format!("Invalid vertex line: {}", line)),
-                            }
+                        let [x, y, z] = parse_floats(&mut tokens, line)?;
+                        vertexes.push(Vertex::new(x, y, z));
+                    },
+                    "vn" => {
+                        let [x, y, z] = parse_floats(&mut tokens, line)?;
+                        normals.push(Vector::new(x, y, z).normalized());
+                    },
+                    "vt" => {
+                        // A third coordinate for 3D textures is allowed and ignored
+                        let mut uv = [0.0; 2];
+                        for c in uv.iter_mut() {
+                            *c = match tokens.next().map(|t| t.parse::<f32>()) {
+                                Some(Ok(v)) => v,
+                                Some(Err(e)) => return Err(e.to_string()),
+                                None => return Err(format!("Invalid texture coordinate line: {}", line)),
+                            };
                         }
-                        vertexes.push(Vertex::new(components[0], components[1], components[2]));
+                        uvs.push(uv);
                     },
                     "f" => {
-                        let mut components: Vec<Vertex> = Vec::new();
+                        // Corners are v, v/vt, v//vn or v/vt/vn
+                        let mut verts = [0usize; 3];
+                        let mut corner_normals: [Option<usize>; 3] = [None; 3];
+                        let mut corner_uvs: [Option<usize>; 3] = [None; 3];
                         for i in 0..3 {
-                            match tokens.next() {
-                                Some(token) => components.push(vertexes[match token.split('/').next().unwrap().parse::<usize>() {
-                                    Ok(n) => n,
-                                    Err(e) => return Err(e.to_string()),
-                                } - 1]),
+                            let mut indexes = match tokens.next() {
+                                Some(token) => token.split('/'),
                                 None => return Err(format!("Invalid face line: {}", line)),
+                            };
+                            verts[i] = match parse_index(indexes.next(), vertexes.len(), line)? {
+                                Some(n) => n,
+                                None => return Err(format!("Invalid face line: {}", line)),
+                            };
+                            corner_uvs[i] = parse_index(indexes.next(), uvs.len(), line)?;
+                            corner_normals[i] = parse_index(indexes.next(), normals.len(), line)?;
+                        }
+                        let mut face = Face::new(verts, stroke);
+                        if let [Some(a), Some(b), Some(c)] = corner_normals {
+                            face = face.with_normals([a, b, c]);
+                        }
+                        if let [Some(a), Some(b), Some(c)] = corner_uvs {
+                            face = face.with_uvs([a, b, c]);
+                        }
+                        faces.push(face)
+                    }
+                    "mtllib" => {
+                        for name in tokens {
+                            let mtl_path = path.with_file_name(name);
+                            // A missing material library only loses the colours, so the mesh still loads in white
+                            match parse_materials(&mtl_path) {
+                                Ok(parsed) => materials.extend(parsed),
+                                Err(e) => log_disp(0, format!("couldn't load materials from {}: {}", mtl_path.display(), e)),
                             }
                         }
-                        faces.push(Triangle::from_vertexes(components[0], components[1], components[2], Stroke::new([255, 255, 255], '█')))
+                    }
+                    "usemtl" => {
+                        let color = match tokens.next().and_then(|name| materials.get(name)) {
+                            Some(color) => *color,
+                            None => [255, 255, 255],
+                        };
+                        stroke = Stroke::new(color, stroke.tex);
                     }
                     _ => {},
                 }
@@ -54,5 +97,71 @@ pub fn parse_mesh(path: &Path) -> Result<Mesh, String> {
         }
     }
     
-    Ok(Mesh::new(faces))
-}
\ No newline at end of file
+    Ok(Mesh::with_normals(vertexes, normals, faces).with_uvs(uvs))
+}
+
+fn parse_floats<'a, I: Iterator<Item = &'a str>>(tokens: &mut I, line: &str) -> Result<[f32; 3], String> {
+    let mut components = [0.0; 3];
+    for c in components.iter_mut() {
+        *c = match tokens.next() {
+            Some(token) => match token.parse::<f32>() {
+                Ok(v) => v,
+                Err(e) => return Err(e.to_string()),
+            },
+            None => return Err(format!("Invalid vertex line: {}", line)),
+        }
+    }
+    Ok(components)
+}
+
+// OBJ indexes are 1-based; an empty or missing index means the attribute is absent
+fn parse_index(token: Option<&str>, len: usize, line: &str) -> Result<Option<usize>, String> {
+    match token {
+        None | Some("") => Ok(None),
+        Some(token) => match token.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= len => Ok(Some(n - 1)),
+            Ok(n) => Err(format!("Index {} out of range: {}", n, line)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+pub fn parse_materials(path: &Path) -> Result<HashMap<String, [u8; 3]>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut materials: HashMap<String, [u8; 3]> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in contents.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("newmtl") => {
+                let name = match tokens.next() {
+                    Some(name) => name.to_string(),
+                    None => return Err(format!("Invalid material line: {}", line)),
+                };
+                materials.insert(name.clone(), [255, 255, 255]);
+                current = Some(name);
+            }
+            Some("Kd") => {
+                let mut color = [0u8; 3];
+                for c in color.iter_mut() {
+                    *c = match tokens.next().map(|t| t.parse::<f32>()) {
+                        Some(Ok(v)) => (v.clamp(0.0, 1.0) * 255.0).round() as u8,
+                        Some(Err(e)) => return Err(e.to_string()),
+                        None => return Err(format!("Invalid diffuse line: {}", line)),
+                    };
+                }
+                if let Some(name) = &current {
+                    materials.insert(name.clone(), color);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
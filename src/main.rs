@@ -22,14 +22,15 @@ use math::triangle::Triangle;
 use math::vector::Vector;
 use math::vertex::Vertex;
 use rendering::camera::Camera;
-use math::mesh::Mesh;
-use crate::interface::input::Input;
+use math::mesh::{Mesh, NormalWeighting};
+use crate::interface::input::{Input, DEFAULT_CREASE_ANGLE};
 use crate::interface::input_context::InputContext;
 use crate::interface::parser::parse_mesh;
 use crate::math::projection_type::ProjectionType;
 use crate::rendering::object::Object;
 use crate::rendering::point::Point;
 use crate::rendering::render_buffer::RenderBuffer;
+use crate::rendering::render_settings::RenderSettings;
 use crate::rendering::stroke::Stroke;
 
 const FRAME_TIME: Duration = Duration::from_micros(16_667);
@@ -62,21 +63,29 @@ fn main() {
     let mut rot = 0.1;
     let mut pos = 1.0;
     let mut prj_type = ProjectionType::Perspective;
+    let mut settings = RenderSettings::new();
     let mut mv_mode = MoveMode::Rotation;
 
     let tetrahedron_mesh = match parse_mesh(&Path::new("assets/tetrahedron.obj")) {
         Ok(mesh) => mesh,
         Err(e) => panic!("{}", e)
     };
-    let tetrahedron = Object::new(tetrahedron_mesh * size);
-    // render_buffer.add_mesh_worldspace(tetrahedron, &cam);
+    let mut tetrahedron = Object::new(tetrahedron_mesh);
+    tetrahedron.set_scale(Vector::new(size, size, size));
+    // render_buffer.add_mesh_worldspace(tetrahedron);
 
     let cube_mesh = match parse_mesh(&Path::new("assets/torus.obj")) {
         Ok(mesh) => mesh,
         Err(e) => panic!("{}", e)
     };
-    let cube = Object::new(cube_mesh * size);
-    render_buffer.add_mesh_worldspace(cube, &cam);
+    let cube_mesh = if cube_mesh.normals.is_empty() {
+        cube_mesh.compute_normals(NormalWeighting::Angle, DEFAULT_CREASE_ANGLE.to_radians())
+    } else {
+        cube_mesh
+    };
+    let mut cube = Object::new(cube_mesh);
+    cube.set_scale(Vector::new(size, size, size));
+    render_buffer.add_mesh_worldspace(cube);
 
     let red = Stroke::new([255, 0, 0], '█');
     let blue = Stroke::new([0, 0, 255], '█');
@@ -86,15 +95,16 @@ fn main() {
         Vertex::new(0.0, 1.0, 0.0),
         red
     ) * 10.0;
-    let m = Mesh::new([t + Vector::new(10.0, 0.0, 0.0), t.with_stroke(blue) + Vector::new(-10.0, 0.0, 0.0)].to_vec());
+    let m = Mesh::from_triangles([t + Vector::new(10.0, 0.0, 0.0), t.with_stroke(blue) + Vector::new(-10.0, 0.0, 0.0)].to_vec());
     let o = Object::new(m);
-    // render_buffer.add_mesh_worldspace(o, &cam);
+    // render_buffer.add_mesh_worldspace(o);
     
     'frame: loop {
         let ctx = &mut InputContext {
             camera: &mut cam,
             buffer: &mut render_buffer,
             projection_type: &mut prj_type,
+            settings: &mut settings,
             exit: &mut false,
         };
 
@@ -107,8 +117,7 @@ fn main() {
         }
         
         render_buffer.pass_obj_time(FRAME_TIME);
-        RenderBuffer::write_meshes_to_buffer(render_buffer.meshes_from_objects(),
-            &mut screen_buffer, &prj_type, &cam);
+        render_buffer.render(&mut screen_buffer, &prj_type, &cam, &settings);
 
         log_disp(1, &input);
         log(2, &prj_type);
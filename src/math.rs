@@ -2,8 +2,14 @@ pub mod geometry;
 pub mod projection_type;
 pub mod vertex;
 pub mod triangle;
-mod rotation;
+pub mod rotation;
+pub mod rotation_order;
 pub mod euler_rotation;
 pub mod quaternion;
 pub mod vector;
-pub mod mesh;
\ No newline at end of file
+pub mod mesh;
+pub mod primitives;
+pub mod transform;
+pub mod matrix;
+pub mod aabb;
+pub mod obb;
\ No newline at end of file
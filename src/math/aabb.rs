@@ -0,0 +1,83 @@
+use crate::math::vector::Vector;
+
+// Corner pairs joined by a box's edges, for corners numbered with x in bit 0, y in bit 1 and z in bit 2
+const EDGES: [[usize; 2]; 12] = [
+    [0, 1], [2, 3], [4, 5], [6, 7],
+    [0, 2], [1, 3], [4, 6], [5, 7],
+    [0, 4], [1, 5], [2, 6], [3, 7],
+];
+
+// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Self {
+        Self { min, max }
+    }
+    // A box around no points is empty at the origin
+    pub fn from_points<I: IntoIterator<Item = Vector>>(points: I) -> Self {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(first) => first,
+            None => return Self::new(Vector::zero(), Vector::zero()),
+        };
+        points.fold(Self::new(first, first), |aabb, p| Self::new(
+            Vector::new(aabb.min.x.min(p.x), aabb.min.y.min(p.y), aabb.min.z.min(p.z)),
+            Vector::new(aabb.max.x.max(p.x), aabb.max.y.max(p.y), aabb.max.z.max(p.z)),
+        ))
+    }
+    pub fn center(&self) -> Vector {
+        (self.min + self.max) / 2.0
+    }
+    pub fn half_extents(&self) -> Vector {
+        (self.max - self.min) / 2.0
+    }
+    pub fn corners(&self) -> [Vector; 8] {
+        std::array::from_fn(|i| Vector::new(
+            if i & 1 == 0 { self.min.x } else { self.max.x },
+            if i & 2 == 0 { self.min.y } else { self.max.y },
+            if i & 4 == 0 { self.min.z } else { self.max.z },
+        ))
+    }
+    pub fn edges(&self) -> [[Vector; 2]; 12] {
+        box_edges(&self.corners())
+    }
+}
+
+// The twelve edges of a box from its corners, numbered as Aabb::corners numbers them
+pub fn box_edges(corners: &[Vector; 8]) -> [[Vector; 2]; 12] {
+    EDGES.map(|[a, b]| [corners[a], corners[b]])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::mesh::Mesh;
+    use crate::math::vector::Vector;
+    use super::Aabb;
+
+    #[test]
+    fn contains_every_point_it_was_built_from() {
+        let mesh = Mesh::torus(3.0, 1.0, 16, 8) + Vector::new(5.0, -2.0, 1.0);
+        let aabb = mesh.bounds();
+        for v in &mesh.vertexes {
+            assert!(v.x >= aabb.min.x && v.y >= aabb.min.y && v.z >= aabb.min.z);
+            assert!(v.x <= aabb.max.x && v.y <= aabb.max.y && v.z <= aabb.max.z);
+        }
+        // Tight: the torus reaches out to R + r in x and z and r up and down
+        assert!((aabb.half_extents() - Vector::new(4.0, 1.0, 4.0)).len() < 1e-4);
+        assert!((aabb.center() - Vector::new(5.0, -2.0, 1.0)).len() < 1e-4);
+    }
+
+    #[test]
+    fn edges_run_along_one_axis_each() {
+        let aabb = Aabb::new(Vector::new(-1.0, -2.0, -3.0), Vector::new(1.0, 2.0, 3.0));
+        let mut lengths: Vec<f32> = aabb.edges().iter().map(|[a, b]| (*b - *a).len()).collect();
+        lengths.sort_by(f32::total_cmp);
+        assert_eq!(lengths, [2.0, 2.0, 2.0, 2.0, 4.0, 4.0, 4.0, 4.0, 6.0, 6.0, 6.0, 6.0]);
+        assert!(Aabb::from_points([]).half_extents().len() == 0.0);
+    }
+}
@@ -1,17 +1,25 @@
 use std::ops::{AddAssign, Mul, MulAssign};
 use crate::math::quaternion::Quaternion;
 use crate::math::rotation::Rotation;
+use crate::math::rotation_order::RotationOrder;
 use crate::math::vector::Vector;
-use crate::math::vertex::Vertex;
 
+#[derive(Debug, Copy, Clone)]
 pub struct EulerRotation {
     pub x: f32,
     pub y: f32,
     pub z: f32,
+    pub order: RotationOrder,
 }
 impl EulerRotation {
     pub fn new(x: f32, y: f32, z: f32) -> Self {
-        Self { x, y, z }
+        Self { x, y, z, order: RotationOrder::default() }
+    }
+    pub fn with_order(self, order: RotationOrder) -> Self {
+        Self { order, ..self }
+    }
+    pub fn angles(&self) -> [f32; 3] {
+        [self.x, self.y, self.z]
     }
     pub fn from_degrees(x: f32, y: f32, z: f32) -> Self {
         Self::new(x.to_radians(), y.to_radians(), z.to_radians())
@@ -22,8 +30,7 @@ impl EulerRotation {
 }
 impl Rotation for EulerRotation {
     fn rotate_vector(&self, v: Vector) -> Vector {
-        let q: Quaternion = v.into();
-        q * v
+        Quaternion::from(*self) * v
     }
 }
 
@@ -36,7 +43,7 @@ impl From<Vector> for EulerRotation {
 impl Mul<f32> for EulerRotation {
     type Output = EulerRotation;
     fn mul(self, rhs: f32) -> Self::Output {
-        EulerRotation::new(self.x * rhs, self.y * rhs, self.z * rhs)
+        EulerRotation::new(self.x * rhs, self.y * rhs, self.z * rhs).with_order(self.order)
     }
 }
 
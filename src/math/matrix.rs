@@ -0,0 +1,217 @@
+use std::ops;
+use crate::math::quaternion::Quaternion;
+use crate::math::transform::Transform;
+use crate::math::vector::Vector;
+use crate::math::vertex::Vertex;
+
+// Row-major, applied to column vectors: v' = M * v. The camera looks down +z (left-handed), and
+// clip space depth runs from 0 at the near plane to w at the far plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f32; 4]; 4]) -> Matrix4 {
+        Matrix4 { m }
+    }
+    pub fn identity() -> Matrix4 {
+        Matrix4::from_scale(Vector::new(1.0, 1.0, 1.0))
+    }
+    pub fn from_translation(t: Vector) -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, t.x],
+            [0.0, 1.0, 0.0, t.y],
+            [0.0, 0.0, 1.0, t.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    pub fn from_scale(s: Vector) -> Matrix4 {
+        Matrix4::new([
+            [s.x, 0.0, 0.0, 0.0],
+            [0.0, s.y, 0.0, 0.0],
+            [0.0, 0.0, s.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    pub fn from_quaternion(q: Quaternion) -> Matrix4 {
+        q.into()
+    }
+    pub fn from_trs(translation: Vector, rotation: Quaternion, scale: Vector) -> Matrix4 {
+        Matrix4::from_translation(translation) * Matrix4::from_quaternion(rotation) * Matrix4::from_scale(scale)
+    }
+    pub fn from_transform(t: &Transform) -> Matrix4 {
+        Matrix4::from_trs(t.translation + t.pivot, t.rotation, t.scale) * Matrix4::from_translation(-t.pivot)
+    }
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Matrix4 {
+        let f = 1.0 / (fov_y / 2.0).tan();
+        let depth = far / (far - near);
+        Matrix4::new([
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, depth, -near * depth],
+            [0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+    pub fn orthographic(width: f32, height: f32, near: f32, far: f32) -> Matrix4 {
+        let depth = 1.0 / (far - near);
+        Matrix4::new([
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, 2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, depth, -near * depth],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    // View matrix for an eye at `eye` looking at `target`
+    pub fn look_at(eye: Vector, target: Vector, up: Vector) -> Matrix4 {
+        let z = (target - eye).normalized();
+        let x = up.cross(z).normalized();
+        let y = z.cross(x);
+        Matrix4::new([
+            [x.x, x.y, x.z, -x.dot(eye)],
+            [y.x, y.y, y.z, -y.dot(eye)],
+            [z.x, z.y, z.z, -z.dot(eye)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = self.m[c][r];
+            }
+        }
+        Matrix4::new(m)
+    }
+    // Gauss-Jordan elimination with partial pivoting; None if the matrix is singular
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.m;
+        let mut inv = Matrix4::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs())).unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for c in 0..4 {
+                a[col][c] *= scale;
+                inv[col][c] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for c in 0..4 {
+                    a[row][c] -= factor * a[col][c];
+                    inv[row][c] -= factor * inv[col][c];
+                }
+            }
+        }
+
+        Some(Matrix4::new(inv))
+    }
+    pub fn transform_point(&self, v: [f32; 4]) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for (r, value) in out.iter_mut().enumerate() {
+            *value = (0..4).map(|c| self.m[r][c] * v[c]).sum();
+        }
+        out
+    }
+    // Affine transform of a position, ignoring any projective row
+    pub fn transform_vertex(&self, v: Vertex) -> Vertex {
+        let [x, y, z, _] = self.transform_point([v.x, v.y, v.z, 1.0]);
+        Vertex::new(x, y, z)
+    }
+    pub fn transform_vector(&self, v: Vector) -> Vector {
+        let [x, y, z, _] = self.transform_point([v.x, v.y, v.z, 0.0]);
+        Vector::new(x, y, z)
+    }
+    // Maps normals so they stay perpendicular to surfaces under non-uniform scale and shear
+    pub fn normal_matrix(&self) -> Matrix4 {
+        match self.inverse() {
+            Some(inverse) => inverse.transpose(),
+            None => *self,
+        }
+    }
+}
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[r][k] * rhs.m[k][c]).sum();
+            }
+        }
+        Matrix4::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::quaternion::Quaternion;
+    use crate::math::transform::Transform;
+    use crate::math::vector::Vector;
+    use crate::math::vertex::Vertex;
+    use super::Matrix4;
+
+    fn close(a: &Matrix4, b: &Matrix4) -> bool {
+        a.m.iter().flatten().zip(b.m.iter().flatten()).all(|(x, y)| (x - y).abs() < 1e-4)
+    }
+
+    // Clip space position after the perspective divide
+    fn project(m: &Matrix4, v: [f32; 3]) -> [f32; 3] {
+        let [x, y, z, w] = m.transform_point([v[0], v[1], v[2], 1.0]);
+        [x / w, y / w, z / w]
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let t = Transform {
+            translation: Vector::new(3.0, -2.0, 7.0),
+            rotation: Quaternion::from_axis_angle(Vector::new(1.0, 1.0, 0.0).normalized(), 0.9),
+            scale: Vector::new(2.0, 0.5, 3.0),
+            pivot: Vector::new(1.0, 0.0, 0.0),
+        };
+        for m in [Matrix4::from_transform(&t), Matrix4::perspective(1.0, 2.0, 0.1, 100.0)] {
+            let inverse = m.inverse().unwrap();
+            assert!(close(&(m * inverse), &Matrix4::identity()));
+            assert!(close(&(inverse * m), &Matrix4::identity()));
+        }
+        assert_eq!(Matrix4::from_scale(Vector::new(1.0, 0.0, 1.0)).inverse(), None);
+    }
+
+    #[test]
+    fn perspective_maps_the_frustum_to_clip_space() {
+        let m = Matrix4::perspective(90f32.to_radians(), 2.0, 1.0, 101.0);
+        // Depth runs from 0 at the near plane to 1 at the far plane
+        assert!((project(&m, [0.0, 0.0, 1.0])[2]).abs() < 1e-6);
+        assert!((project(&m, [0.0, 0.0, 101.0])[2] - 1.0).abs() < 1e-6);
+        // The top edge of a 90 degree view is as far up as it is away, and the sides twice that
+        let [x, y, _] = project(&m, [20.0, 10.0, 10.0]);
+        assert!((x - 1.0).abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+
+        let ortho = Matrix4::orthographic(8.0, 4.0, 0.0, 10.0);
+        assert_eq!(project(&ortho, [4.0, -2.0, 5.0]), [1.0, -1.0, 0.5]);
+    }
+
+    #[test]
+    fn look_at_puts_the_target_straight_ahead() {
+        let eye = Vector::new(5.0, 5.0, 0.0);
+        let view = Matrix4::look_at(eye, Vector::new(5.0, 5.0, 10.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(close(&view, &Matrix4::from_translation(-eye)));
+
+        let view = Matrix4::look_at(eye, Vector::zero(), Vector::new(0.0, 1.0, 0.0));
+        let target = view.transform_vertex(Vertex::zero());
+        assert!(target.x.abs() < 1e-5 && target.y.abs() < 1e-5);
+        assert!((target.z - eye.len()).abs() < 1e-5);
+        // Up stays up, looking down at the target
+        assert!(view.transform_vector(Vector::new(0.0, 1.0, 0.0)).y > 0.0);
+    }
+}
@@ -1,39 +1,272 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::ops;
-use std::path::Path;
-use crate::math::rotation::Rotation;
+use std::rc::Rc;
+use crate::math::aabb::Aabb;
+use crate::math::matrix::Matrix4;
 use crate::math::triangle::Triangle;
 use crate::math::vector::Vector;
 use crate::math::vertex::Vertex;
+use crate::rendering::stroke::Stroke;
 
+#[derive(Debug, Clone, Copy)]
+pub struct Face {
+    pub verts: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub stroke: Stroke,
+}
+
+impl Face {
+    pub fn new(verts: [usize; 3], stroke: Stroke) -> Self {
+        Self { verts, normals: None, uvs: None, stroke }
+    }
+    pub fn with_normals(&self, normals: [usize; 3]) -> Self {
+        Self { normals: Some(normals), ..*self }
+    }
+    pub fn with_uvs(&self, uvs: [usize; 3]) -> Self {
+        Self { uvs: Some(uvs), ..*self }
+    }
+}
+
+// An edge between two vertexes and the faces that share it, usually one on a boundary and two elsewhere
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub verts: [usize; 2],
+    pub faces: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NormalWeighting {
+    Area,
+    Angle,
+}
+
+// Vertexes and normals are stored once and shared between faces; the face list never changes when
+// the mesh is transformed, so it is reference counted instead of copied.
 #[derive(Debug, Clone)]
 pub struct Mesh {
-    pub tris: Vec<Triangle>,
+    pub vertexes: Vec<Vertex>,
+    pub normals: Vec<Vector>,
+    // Texture coordinates, with v = 0 at the bottom of the image
+    pub uvs: Vec<[f32; 2]>,
+    pub faces: Rc<[Face]>,
+    // Worked out on first use. Anything that moves vertexes builds a new mesh, which starts without it.
+    bounds: OnceCell<Aabb>,
+    // Also worked out on first use, but only depends on the faces, so it's shared along with them
+    edges: Rc<OnceCell<Vec<Edge>>>,
 }
 
 impl Mesh {
-    pub fn new(tris: Vec<Triangle>) -> Self {
-        Self { tris }
+    pub fn new(vertexes: Vec<Vertex>, faces: Vec<Face>) -> Self {
+        Self::with_normals(vertexes, Vec::new(), faces)
+    }
+    pub fn with_normals(vertexes: Vec<Vertex>, normals: Vec<Vector>, faces: Vec<Face>) -> Self {
+        Self { vertexes, normals, uvs: Vec::new(), faces: faces.into(), bounds: OnceCell::new(), edges: Rc::new(OnceCell::new()) }
+    }
+    pub fn with_uvs(self, uvs: Vec<[f32; 2]>) -> Self {
+        Self { uvs, ..self }
+    }
+    // Builds an indexed mesh from a triangle soup, welding vertexes with identical positions
+    pub fn from_triangles(tris: Vec<Triangle>) -> Self {
+        let mut vertexes: Vec<Vertex> = Vec::new();
+        let mut indexes: HashMap<[u32; 3], usize> = HashMap::new();
+        let faces = tris.iter().map(|t| {
+            let verts = t.verts.map(|v| {
+                *indexes.entry([v.x.to_bits(), v.y.to_bits(), v.z.to_bits()]).or_insert_with(|| {
+                    vertexes.push(v);
+                    vertexes.len() - 1
+                })
+            });
+            Face::new(verts, t.stroke)
+        }).collect();
+        Self::new(vertexes, faces)
+    }
+    pub fn triangle(&self, face: &Face) -> Triangle {
+        Triangle::from_array(face.verts.map(|i| self.vertexes[i]), face.stroke)
+    }
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        self.faces.iter().map(|f| self.triangle(f))
+    }
+    pub fn map_vertexes<F: Fn(Vertex) -> Vertex>(&self, f: F) -> Self {
+        Self {
+            vertexes: self.vertexes.iter().map(|v| f(*v)).collect(),
+            normals: self.normals.clone(),
+            uvs: self.uvs.clone(),
+            faces: self.faces.clone(),
+            bounds: OnceCell::new(),
+            edges: self.edges.clone(),
+        }
     }
     pub fn translate(&self, v: &Vector) -> Self {
-        let tris: Vec<Triangle> = self.tris.iter().map(|t| t.translate(v)).collect();
-        Self::new(tris)
+        self.map_vertexes(|vtx| vtx + *v)
+    }
+    pub fn transform_matrix(&self, m: &Matrix4) -> Self {
+        let normal_matrix = m.normal_matrix();
+        Self {
+            vertexes: self.vertexes.iter().map(|v| m.transform_vertex(*v)).collect(),
+            normals: self.normals.iter().map(|n| normal_matrix.transform_vector(*n).normalized()).collect(),
+            uvs: self.uvs.clone(),
+            faces: self.faces.clone(),
+            bounds: OnceCell::new(),
+            edges: self.edges.clone(),
+        }
     }
-    pub fn rotate<R: Rotation>(&self, rot: &R) -> Self {
-        let tris = self.tris.iter().map(|t| t.rotate(rot)).collect();
-        Self::new(tris)
+    pub fn face_normal(&self, face: &Face) -> Vector {
+        self.triangle(face).normal()
+    }
+    pub fn bounds(&self) -> Aabb {
+        *self.bounds.get_or_init(|| Aabb::from_points(self.vertexes.iter().map(|v| Vector::from(*v))))
+    }
+    // Replaces the vertex normals by averaging the normals of the faces around each vertex. Faces whose
+    // normals differ by more than crease_angle (radians) from the face being shaded are left out, so
+    // hard edges stay sharp.
+    pub fn compute_normals(&self, weighting: NormalWeighting, crease_angle: f32) -> Self {
+        let face_normals: Vec<Vector> = self.faces.iter().map(|f| self.face_normal(f)).collect();
+
+        let mut adjacent: Vec<Vec<(usize, f32)>> = vec![Vec::new(); self.vertexes.len()];
+        for (i, face) in self.faces.iter().enumerate() {
+            for corner in 0..3 {
+                let weight = match weighting {
+                    NormalWeighting::Area => {
+                        let [a, b, c] = face.verts.map(|v| self.vertexes[v]);
+                        let ab: Vector = (b - a).into();
+                        let ac: Vector = (c - a).into();
+                        ab.cross(ac).len() / 2.0
+                    }
+                    NormalWeighting::Angle => {
+                        let p = self.vertexes[face.verts[corner]];
+                        let a: Vector = (self.vertexes[face.verts[(corner + 1) % 3]] - p).into();
+                        let b: Vector = (self.vertexes[face.verts[(corner + 2) % 3]] - p).into();
+                        (a.normalized().dot(b.normalized())).clamp(-1.0, 1.0).acos()
+                    }
+                };
+                if weight.is_finite() && face_normals[i].len().is_finite() {
+                    adjacent[face.verts[corner]].push((i, weight));
+                }
+            }
+        }
+
+        let min_cos = crease_angle.cos();
+        let mut normals: Vec<Vector> = Vec::new();
+        let mut indexes: HashMap<(usize, [u32; 3]), usize> = HashMap::new();
+        let faces = self.faces.iter().enumerate().map(|(i, face)| {
+            let corners = face.verts.map(|v| {
+                let mut sum = Vector::zero();
+                for (other, weight) in &adjacent[v] {
+                    if face_normals[i].dot(face_normals[*other]) >= min_cos {
+                        sum += face_normals[*other] * *weight;
+                    }
+                }
+                let normal = if sum.len() > 0.0 { sum.normalized() } else { face_normals[i] };
+                *indexes.entry((v, [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()])).or_insert_with(|| {
+                    normals.push(normal);
+                    normals.len() - 1
+                })
+            });
+            face.with_normals(corners)
+        }).collect();
+
+        Self::with_normals(self.vertexes.clone(), normals, faces).with_uvs(self.uvs.clone())
+    }
+    // Every edge once, in the order faces first use them
+    pub fn edges(&self) -> &[Edge] {
+        self.edges.get_or_init(|| self.build_edges())
+    }
+    fn build_edges(&self) -> Vec<Edge> {
+        let mut edges: Vec<Edge> = Vec::new();
+        let mut indexes: HashMap<[usize; 2], usize> = HashMap::new();
+        for (i, face) in self.faces.iter().enumerate() {
+            for corner in 0..3 {
+                let a = face.verts[corner];
+                let b = face.verts[(corner + 1) % 3];
+                let key = [a.min(b), a.max(b)];
+                let index = *indexes.entry(key).or_insert_with(|| {
+                    edges.push(Edge { verts: key, faces: Vec::with_capacity(2) });
+                    edges.len() - 1
+                });
+                edges[index].faces.push(i);
+            }
+        }
+        edges
     }
 }
 
 impl ops::Add<Vector> for Mesh {
     type Output = Self;
     fn add(self, rhs: Vector) -> Self::Output {
-        Self::new(self.tris.iter().map(|t| *t + rhs).collect())
+        self.translate(&rhs)
     }
 }
 
 impl ops::Mul<f32> for Mesh {
     type Output = Self;
     fn mul(self, rhs: f32) -> Self::Output {
-        Self::new(self.tris.iter().map(|t| *t * rhs).collect())
+        self.map_vertexes(|v| v * rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::math::matrix::Matrix4;
+    use crate::math::triangle::Triangle;
+    use crate::math::vector::Vector;
+    use crate::math::vertex::Vertex;
+    use crate::rendering::stroke::Stroke;
+    use super::{Mesh, NormalWeighting};
+
+    // A unit square in the xy plane, split along its diagonal
+    fn square() -> Mesh {
+        let [a, b, c, d] = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| Vertex::new(x, y, 0.0));
+        let stroke = Stroke::filled([255; 3]);
+        Mesh::from_triangles(vec![Triangle::from_array([a, b, c], stroke), Triangle::from_array([a, c, d], stroke)])
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn triangle_soup_welds_shared_corners() {
+        let mesh = square();
+        assert_eq!(mesh.vertexes.len(), 4);
+        assert_eq!(mesh.faces.len(), 2);
+        // The diagonal is one edge with a face on either side
+        assert_eq!(mesh.faces[0].verts[0], mesh.faces[1].verts[0]);
+        assert_eq!(mesh.faces[0].verts[2], mesh.faces[1].verts[1]);
+        assert_eq!(mesh.edges().iter().filter(|e| e.faces.len() == 2).count(), 1);
+        // The triangle view gives back the corners that went in
+        let tris: Vec<Triangle> = mesh.triangles().collect();
+        assert_eq!(tris[1].verts[2], Vertex::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn transforms_move_each_vertex_once_and_share_the_faces() {
+        let mesh = square();
+        let moved = mesh.transform_matrix(&Matrix4::from_translation(Vector::new(0.0, 0.0, 5.0)));
+        assert_eq!(moved.vertexes.len(), mesh.vertexes.len());
+        assert!(moved.vertexes.iter().zip(&mesh.vertexes).all(|(m, v)| *m == Vertex::new(v.x, v.y, v.z + 5.0)));
+        assert!(Rc::ptr_eq(&moved.faces, &mesh.faces));
+        let scaled = mesh * 2.0;
+        assert!((scaled.bounds().max - Vector::new(2.0, 2.0, 0.0)).len() < 1e-6);
+    }
+
+    #[test]
+    fn computed_normals_respect_the_crease_angle() {
+        let cube = Mesh::cuboid(2.0, 2.0, 2.0);
+        // Sides meet at right angles, so a smaller crease angle keeps every face flat
+        let hard = cube.compute_normals(NormalWeighting::Angle, 30f32.to_radians());
+        for face in hard.faces.iter() {
+            let expected = hard.face_normal(face);
+            for n in face.normals.unwrap() {
+                assert!((hard.normals[n] - expected).len() < 1e-5);
+            }
+        }
+        // Averaged by corner angle, each of the three sides at a corner counts the same however it's split
+        let smooth = cube.compute_normals(NormalWeighting::Angle, 180f32.to_radians());
+        for face in smooth.faces.iter() {
+            for (v, n) in face.verts.iter().zip(face.normals.unwrap()) {
+                let diagonal = Vector::from(smooth.vertexes[*v]).normalized();
+                assert!((smooth.normals[n] - diagonal).len() < 1e-5);
+            }
+        }
+        assert_eq!(smooth.normals.len(), 8);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::math::aabb::{box_edges, Aabb};
+use crate::math::matrix::Matrix4;
+use crate::math::vector::Vector;
+
+// Oriented bounding box: a mesh's Aabb carried through its object's transform, so it turns and
+// stretches with the object instead of growing to fit it
+#[derive(Debug, Clone, Copy)]
+pub struct Obb {
+    pub center: Vector,
+    // From the centre to the middle of a face along each of the box's axes, scale included
+    pub axes: [Vector; 3],
+}
+
+impl Obb {
+    pub fn new(center: Vector, axes: [Vector; 3]) -> Self {
+        Self { center, axes }
+    }
+    pub fn from_aabb(aabb: &Aabb, m: &Matrix4) -> Self {
+        let half = aabb.half_extents();
+        let center = Vector::from(m.transform_vertex(aabb.center().into()));
+        Self::new(center, [
+            m.transform_vector(Vector::new(half.x, 0.0, 0.0)),
+            m.transform_vector(Vector::new(0.0, half.y, 0.0)),
+            m.transform_vector(Vector::new(0.0, 0.0, half.z)),
+        ])
+    }
+    // Numbered as Aabb::corners numbers them
+    pub fn corners(&self) -> [Vector; 8] {
+        std::array::from_fn(|i| {
+            let [x, y, z] = [1, 2, 4].map(|bit| if i & bit == 0 { -1.0 } else { 1.0 });
+            self.center + self.axes[0] * x + self.axes[1] * y + self.axes[2] * z
+        })
+    }
+    pub fn edges(&self) -> [[Vector; 2]; 12] {
+        box_edges(&self.corners())
+    }
+    // The smallest world axis-aligned box around this one
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.corners())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::matrix::Matrix4;
+    use crate::math::mesh::Mesh;
+    use crate::math::quaternion::Quaternion;
+    use crate::math::vector::Vector;
+    use super::Obb;
+
+    // Whether a point is inside the box, measured along each of its axes
+    fn contains(obb: &Obb, point: Vector) -> bool {
+        let offset = point - obb.center;
+        obb.axes.iter().all(|axis| offset.dot(*axis).abs() <= axis.dot(*axis) + 1e-4)
+    }
+
+    #[test]
+    fn turns_with_the_object_and_holds_its_mesh() {
+        let mesh = Mesh::cone(2.0, 5.0, 12);
+        let rotation = Quaternion::from_axis_angle(Vector::new(1.0, 1.0, 0.0).normalized(), 0.8);
+        let model = Matrix4::from_trs(Vector::new(3.0, 0.0, -4.0), rotation, Vector::new(2.0, 1.0, 0.5));
+        let obb = Obb::from_aabb(&mesh.bounds(), &model);
+        for v in &mesh.vertexes {
+            assert!(contains(&obb, Vector::from(model.transform_vertex(*v))));
+        }
+        // Scale stretches the axes but rotation leaves them square to each other
+        let extents = mesh.bounds().half_extents();
+        assert!((obb.axes[0].len() - extents.x * 2.0).abs() < 1e-4);
+        assert!(obb.axes[0].dot(obb.axes[1]).abs() < 1e-4);
+
+        // The world box around it holds all its corners, and touches some of them on every side
+        let aabb = obb.aabb();
+        for corner in obb.corners() {
+            assert!(corner.x >= aabb.min.x - 1e-4 && corner.x <= aabb.max.x + 1e-4);
+            assert!(corner.y >= aabb.min.y - 1e-4 && corner.y <= aabb.max.y + 1e-4);
+            assert!(corner.z >= aabb.min.z - 1e-4 && corner.z <= aabb.max.z + 1e-4);
+        }
+        assert!(obb.corners().iter().any(|c| (c.x - aabb.max.x).abs() < 1e-4));
+        assert!(!contains(&obb, obb.center + obb.axes[2] * 1.1));
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+use crate::math::mesh::{Face, Mesh};
+use crate::math::vector::Vector;
+use crate::math::vertex::Vertex;
+use crate::rendering::stroke::Stroke;
+
+struct MeshBuilder {
+    vertexes: Vec<Vertex>,
+    normals: Vec<Vector>,
+    uvs: Vec<[f32; 2]>,
+    faces: Vec<Face>,
+    stroke: Stroke,
+}
+
+impl MeshBuilder {
+    fn new() -> Self {
+        Self {
+            vertexes: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            faces: Vec::new(),
+            stroke: Stroke::new([255, 255, 255], '█'),
+        }
+    }
+    fn vertex(&mut self, v: Vertex) -> usize {
+        self.vertexes.push(v);
+        self.vertexes.len() - 1
+    }
+    fn normal(&mut self, n: Vector) -> usize {
+        self.normals.push(n.normalized());
+        self.normals.len() - 1
+    }
+    fn uv(&mut self, u: f32, v: f32) -> usize {
+        self.uvs.push([u, v]);
+        self.uvs.len() - 1
+    }
+    fn face(&mut self, verts: [usize; 3], normals: [usize; 3]) {
+        self.oriented_face(verts, normals, None);
+    }
+    fn quad(&mut self, verts: [usize; 4], normals: [usize; 4]) {
+        self.face([verts[0], verts[1], verts[2]], [normals[0], normals[1], normals[2]]);
+        self.face([verts[0], verts[2], verts[3]], [normals[0], normals[2], normals[3]]);
+    }
+    fn textured_quad(&mut self, verts: [usize; 4], normals: [usize; 4], uvs: [usize; 4]) {
+        self.oriented_face([verts[0], verts[1], verts[2]], [normals[0], normals[1], normals[2]], Some([uvs[0], uvs[1], uvs[2]]));
+        self.oriented_face([verts[0], verts[2], verts[3]], [normals[0], normals[2], normals[3]], Some([uvs[0], uvs[2], uvs[3]]));
+    }
+    // Faces are wound so their geometric normal agrees with the supplied vertex normals, which keeps
+    // the generators free of winding bookkeeping. Degenerate faces (at the poles) are dropped; the test is
+    // relative to the edge lengths so it holds at any size.
+    fn oriented_face(&mut self, verts: [usize; 3], normals: [usize; 3], uvs: Option<[usize; 3]>) {
+        let [a, b, c] = verts.map(|i| self.vertexes[i]);
+        let ab: Vector = (b - a).into();
+        let ac: Vector = (c - a).into();
+        let geometric = ab.cross(ac);
+        if geometric.len() <= f32::EPSILON * (ab.dot(ab) + ac.dot(ac)) {
+            return;
+        }
+
+        let mut expected = Vector::zero();
+        for n in normals {
+            expected += self.normals[n];
+        }
+        let order = if geometric.dot(expected) < 0.0 { [0, 2, 1] } else { [0, 1, 2] };
+        let mut face = Face::new(order.map(|i| verts[i]), self.stroke).with_normals(order.map(|i| normals[i]));
+        if let Some(uvs) = uvs {
+            face = face.with_uvs(order.map(|i| uvs[i]));
+        }
+        self.faces.push(face);
+    }
+    fn build(self) -> Mesh {
+        Mesh::with_normals(self.vertexes, self.normals, self.faces).with_uvs(self.uvs)
+    }
+}
+
+impl Mesh {
+    pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Mesh {
+        assert_dimensions(&[radius]);
+        let segments = segments.max(3);
+        let rings = rings.max(2);
+        let mut b = MeshBuilder::new();
+
+        // Positions are shared around the seam and at the poles so the surface is closed, while texture
+        // coordinates need a seam, so they get their own grid as in the torus
+        let north = (b.vertex(Vertex::new(0.0, radius, 0.0)), b.normal(Vector::new(0.0, 1.0, 0.0)));
+        let south = (b.vertex(Vertex::new(0.0, -radius, 0.0)), b.normal(Vector::new(0.0, -1.0, 0.0)));
+        let mut grid = Vec::with_capacity((rings - 1) * segments);
+        for ring in 1..rings {
+            let phi = PI * ring as f32 / rings as f32;
+            for seg in 0..segments {
+                let theta = TAU * seg as f32 / segments as f32;
+                let n = Vector::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+                grid.push((b.vertex((n * radius).into()), b.normal(n)));
+            }
+        }
+        let mut uvs = Vec::with_capacity((rings + 1) * (segments + 1));
+        for ring in 0..=rings {
+            for seg in 0..=segments {
+                uvs.push(b.uv(seg as f32 / segments as f32, 1.0 - ring as f32 / rings as f32));
+            }
+        }
+
+        let at = |ring: usize, seg: usize| match ring {
+            0 => north,
+            r if r == rings => south,
+            r => grid[(r - 1) * segments + seg % segments],
+        };
+        let uv_at = |ring: usize, seg: usize| uvs[ring * (segments + 1) + seg];
+        for ring in 0..rings {
+            for seg in 0..segments {
+                let corners = [(ring, seg), (ring, seg + 1), (ring + 1, seg + 1), (ring + 1, seg)];
+                b.textured_quad(
+                    corners.map(|(r, s)| at(r, s).0),
+                    corners.map(|(r, s)| at(r, s).1),
+                    corners.map(|(r, s)| uv_at(r, s)),
+                );
+            }
+        }
+        b.build()
+    }
+
+    pub fn icosphere(radius: f32, subdivisions: usize) -> Mesh {
+        assert_dimensions(&[radius]);
+        let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let mut points: Vec<Vector> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ].iter().map(|p| Vector::new(p.0, p.1, p.2).normalized()).collect();
+        let mut tris: Vec<[usize; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+            let mut midpoint = |a: usize, b: usize, points: &mut Vec<Vector>| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let mut m = points[a];
+                    m += points[b];
+                    points.push(m.normalized());
+                    points.len() - 1
+                })
+            };
+            tris = tris.iter().flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b, &mut points);
+                let bc = midpoint(b, c, &mut points);
+                let ca = midpoint(c, a, &mut points);
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            }).collect();
+        }
+
+        let mut b = MeshBuilder::new();
+        for p in &points {
+            b.vertex((*p * radius).into());
+            b.normal(*p);
+        }
+        for tri in tris {
+            b.face(tri, tri);
+        }
+        b.build()
+    }
+
+    pub fn cuboid(width: f32, height: f32, depth: f32) -> Mesh {
+        assert_dimensions(&[width, height, depth]);
+        let half = Vector::new(width / 2.0, height / 2.0, depth / 2.0);
+        let mut b = MeshBuilder::new();
+
+        let mut corners = [0usize; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let sign = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+            *corner = b.vertex(Vertex::new(sign(1) * half.x, sign(2) * half.y, sign(4) * half.z));
+        }
+
+        // Each side lists its corners in order around the face, as bit patterns of (x, y, z)
+        let sides: [(Vector, [usize; 4]); 6] = [
+            (Vector::new(-1.0, 0.0, 0.0), [0, 2, 6, 4]),
+            (Vector::new(1.0, 0.0, 0.0), [1, 5, 7, 3]),
+            (Vector::new(0.0, -1.0, 0.0), [0, 4, 5, 1]),
+            (Vector::new(0.0, 1.0, 0.0), [2, 3, 7, 6]),
+            (Vector::new(0.0, 0.0, -1.0), [0, 1, 3, 2]),
+            (Vector::new(0.0, 0.0, 1.0), [4, 6, 7, 5]),
+        ];
+        // Every side shows the whole texture
+        let uvs = [b.uv(0.0, 0.0), b.uv(1.0, 0.0), b.uv(1.0, 1.0), b.uv(0.0, 1.0)];
+        for (normal, quad) in sides {
+            let n = b.normal(normal);
+            b.textured_quad(quad.map(|i| corners[i]), [n; 4], uvs);
+        }
+        b.build()
+    }
+
+    pub fn cylinder(radius: f32, height: f32, segments: usize) -> Mesh {
+        Self::frustum(radius, radius, height, segments)
+    }
+
+    pub fn cone(radius: f32, height: f32, segments: usize) -> Mesh {
+        Self::frustum(radius, 0.0, height, segments)
+    }
+
+    // Shared by cylinder and cone: a capped frustum along y, centred on the origin
+    fn frustum(bottom_radius: f32, top_radius: f32, height: f32, segments: usize) -> Mesh {
+        assert_dimensions(&[bottom_radius, height]);
+        let segments = segments.max(3);
+        let half = height / 2.0;
+        let slope = (bottom_radius - top_radius) / height;
+        let mut b = MeshBuilder::new();
+
+        let down = b.normal(Vector::new(0.0, -1.0, 0.0));
+        let up = b.normal(Vector::new(0.0, 1.0, 0.0));
+        let bottom_center = b.vertex(Vertex::new(0.0, -half, 0.0));
+        let top_center = b.vertex(Vertex::new(0.0, half, 0.0));
+        // A cone's sides all meet at the one apex, though each keeps its own normal there
+        let apex = (top_radius == 0.0).then_some(top_center);
+
+        let mut ring = Vec::with_capacity(segments);
+        for seg in 0..segments {
+            let theta = TAU * seg as f32 / segments as f32;
+            let (sin, cos) = theta.sin_cos();
+            let bottom = b.vertex(Vertex::new(bottom_radius * cos, -half, bottom_radius * sin));
+            let top = apex.unwrap_or_else(|| b.vertex(Vertex::new(top_radius * cos, half, top_radius * sin)));
+            let side = b.normal(Vector::new(cos, slope, sin));
+            ring.push((bottom, top, side));
+        }
+
+        for seg in 0..segments {
+            let (b0, t0, n0) = ring[seg];
+            let (b1, t1, n1) = ring[(seg + 1) % segments];
+            b.quad([b0, b1, t1, t0], [n0, n1, n1, n0]);
+            b.face([bottom_center, b1, b0], [down; 3]);
+            if top_radius > 0.0 {
+                b.face([top_center, t0, t1], [up; 3]);
+            }
+        }
+        b.build()
+    }
+
+    pub fn plane(width: f32, depth: f32, subdivisions: usize) -> Mesh {
+        assert_dimensions(&[width, depth]);
+        let cells = subdivisions + 1;
+        let mut b = MeshBuilder::new();
+        let n = b.normal(Vector::new(0.0, 1.0, 0.0));
+
+        let mut grid = Vec::with_capacity((cells + 1) * (cells + 1));
+        for row in 0..=cells {
+            for col in 0..=cells {
+                let x = width * (col as f32 / cells as f32 - 0.5);
+                let z = depth * (row as f32 / cells as f32 - 0.5);
+                let uv = b.uv(col as f32 / cells as f32, row as f32 / cells as f32);
+                grid.push((b.vertex(Vertex::new(x, 0.0, z)), uv));
+            }
+        }
+
+        let at = |row: usize, col: usize| grid[row * (cells + 1) + col];
+        for row in 0..cells {
+            for col in 0..cells {
+                let corners = [at(row, col), at(row, col + 1), at(row + 1, col + 1), at(row + 1, col)];
+                b.textured_quad(corners.map(|c| c.0), [n; 4], corners.map(|c| c.1));
+            }
+        }
+        b.build()
+    }
+
+    pub fn torus(major_radius: f32, minor_radius: f32, segments: usize, sides: usize) -> Mesh {
+        assert_dimensions(&[major_radius, minor_radius]);
+        let segments = segments.max(3);
+        let sides = sides.max(3);
+        let mut b = MeshBuilder::new();
+
+        let mut grid = Vec::with_capacity(segments * sides);
+        for seg in 0..segments {
+            let theta = TAU * seg as f32 / segments as f32;
+            let (sin_t, cos_t) = theta.sin_cos();
+            for side in 0..sides {
+                let phi = TAU * side as f32 / sides as f32;
+                let (sin_p, cos_p) = phi.sin_cos();
+                let n = Vector::new(cos_p * cos_t, sin_p, cos_p * sin_t);
+                let center = Vector::new(major_radius * cos_t, 0.0, major_radius * sin_t);
+                let mut p = n * minor_radius;
+                p += center;
+                grid.push((b.vertex(p.into()), b.normal(n)));
+            }
+        }
+
+        // Positions wrap around, but texture coordinates need a seam, so they get their own grid
+        let mut uvs = Vec::with_capacity((segments + 1) * (sides + 1));
+        for seg in 0..=segments {
+            for side in 0..=sides {
+                uvs.push(b.uv(seg as f32 / segments as f32, side as f32 / sides as f32));
+            }
+        }
+
+        let at = |seg: usize, side: usize| grid[(seg % segments) * sides + side % sides];
+        let uv_at = |seg: usize, side: usize| uvs[seg * (sides + 1) + side];
+        for seg in 0..segments {
+            for side in 0..sides {
+                let corners = [(seg, side), (seg + 1, side), (seg + 1, side + 1), (seg, side + 1)];
+                b.textured_quad(
+                    corners.map(|(i, j)| at(i, j).0),
+                    corners.map(|(i, j)| at(i, j).1),
+                    corners.map(|(i, j)| uv_at(i, j)),
+                );
+            }
+        }
+        b.build()
+    }
+}
+
+// Zero, negative or infinite sizes make degenerate or inside-out meshes, and NaN normals
+fn assert_dimensions(dimensions: &[f32]) {
+    for d in dimensions {
+        assert!(d.is_finite() && *d > 0.0, "primitive dimensions must be positive, got {}", d);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::mesh::Mesh;
+    use crate::math::vector::Vector;
+
+    // Checks every face and its vertex normals point away from the point of the solid nearest to it
+    fn assert_outward<F: Fn(Vector) -> Vector>(name: &str, mesh: &Mesh, inside: F) {
+        for face in mesh.faces.iter() {
+            let center = face.verts.iter().fold(Vector::zero(), |sum, &v| sum + Vector::from(mesh.vertexes[v])) / 3.0;
+            let outward = center - inside(center);
+            assert!(mesh.face_normal(face).dot(outward) > 0.0, "{}: face {:?} points inward", name, face.verts);
+            for n in face.normals.expect("primitives have vertex normals") {
+                let normal = mesh.normals[n];
+                assert!((normal.len() - 1.0).abs() < 1e-5, "{}: normal {} isn't unit length", name, normal);
+                assert!(normal.dot(outward) > 0.0, "{}: normal {} points inward at face {:?}", name, normal, face.verts);
+            }
+        }
+    }
+
+    fn assert_closed(name: &str, mesh: &Mesh) {
+        for edge in mesh.edges() {
+            assert_eq!(edge.faces.len(), 2, "{}: edge {:?} has {} faces", name, edge.verts, edge.faces.len());
+        }
+    }
+
+    #[test]
+    fn convex_primitives_are_closed_and_face_outward() {
+        let meshes = [
+            ("cuboid", Mesh::cuboid(2.0, 3.0, 4.0)),
+            ("uv sphere", Mesh::uv_sphere(5.0, 16, 8)),
+            ("icosphere", Mesh::icosphere(5.0, 2)),
+            ("cylinder", Mesh::cylinder(2.0, 6.0, 12)),
+            ("cone", Mesh::cone(2.0, 6.0, 12)),
+        ];
+        for (name, mesh) in meshes {
+            assert!(!mesh.faces.is_empty(), "{} has no faces", name);
+            assert_closed(name, &mesh);
+            assert_outward(name, &mesh, |_| Vector::zero());
+        }
+    }
+
+    #[test]
+    fn torus_is_closed_and_faces_away_from_its_core() {
+        let mesh = Mesh::torus(10.0, 3.0, 24, 12);
+        assert_closed("torus", &mesh);
+        assert_outward("torus", &mesh, |p| Vector::new(p.x, 0.0, p.z).normalized() * 10.0);
+    }
+
+    #[test]
+    fn tiny_primitives_keep_their_faces() {
+        assert_eq!(Mesh::uv_sphere(0.001, 16, 8).faces.len(), Mesh::uv_sphere(10.0, 16, 8).faces.len());
+        assert_eq!(Mesh::cone(0.001, 0.002, 12).faces.len(), Mesh::cone(2.0, 6.0, 12).faces.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn flat_cylinder_is_rejected() {
+        Mesh::cylinder(2.0, 0.0, 12);
+    }
+}
@@ -1,6 +1,9 @@
+use std::fmt::{Display, Formatter};
 use std::ops;
 use crate::math::euler_rotation::EulerRotation;
+use crate::math::matrix::Matrix4;
 use crate::math::rotation::Rotation;
+use crate::math::rotation_order::RotationOrder;
 use crate::math::vector::Vector;
 use crate::math::vertex::Vertex;
 
@@ -20,9 +23,8 @@ impl Quaternion {
         let e = EulerRotation::new(x, y, z);
         e.into()
     }
-    pub fn from_euler_vec(v: Vector) -> Quaternion {
-        let e: EulerRotation = v.into();
-        e.into()
+    pub fn from_euler_vec(v: Vector, order: RotationOrder) -> Quaternion {
+        EulerRotation::from(v).with_order(order).into()
     }
     pub fn identity() -> Quaternion {
         Quaternion::new(0.0, 0.0, 0.0, 1.0)
@@ -48,6 +50,19 @@ impl Quaternion {
         self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
     }
 
+    // This rotation applied t times over. The angle is scaled and wrapped in f64, so large t (like
+    // hours of a continuous spin) doesn't lose precision the way slerp's sin(theta * t) does.
+    pub fn powf(&self, t: f64) -> Quaternion {
+        let q = self.normalized();
+        let sin = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if sin < 1e-6 {
+            return Quaternion::identity();
+        }
+        let angle = 2.0 * sin.atan2(q.w) as f64;
+        let angle = (angle * t).rem_euclid(std::f64::consts::TAU);
+        Quaternion::from_axis_angle(Vector::new(q.x / sin, q.y / sin, q.z / sin), angle as f32)
+    }
+
     pub fn slerp(&self, q2: Quaternion, t: f32) -> Quaternion {
         let mut q1 = self.normalized();
         let mut q2 = q2.normalized();
@@ -92,6 +107,13 @@ impl Quaternion {
 
 }
 
+impl Display for Quaternion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let e: EulerRotation = (*self).into();
+        write!(f, "({:.1}°, {:.1}°, {:.1}°)", e.x.to_degrees(), e.y.to_degrees(), e.z.to_degrees())
+    }
+}
+
 impl Rotation for Quaternion {
     fn rotate_vector(&self, v: Vector) -> Vector {
         *self * v
@@ -100,16 +122,25 @@ impl Rotation for Quaternion {
 
 impl From<EulerRotation> for Quaternion {
     fn from(e: EulerRotation) -> Quaternion {
-        let (cx, sx) = ((e.x * 0.5).cos(), (e.x * 0.5).sin());
-        let (cy, sy) = ((e.y * 0.5).cos(), (e.y * 0.5).sin());
-        let (cz, sz) = ((e.z * 0.5).cos(), (e.z * 0.5).sin());
-
-        let x = sx * cy * cz - cx * sy * sz;
-        let y = cx * sy * cz + sx * cy * sz;
-        let z = cx * cy * sz - sx * sy * cz;
-        let w = cx * cy * cz + sx * sy * sz;
-
-        Quaternion { x, y, z, w }
+        let angles = e.angles();
+        let units = [Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 0.0, 1.0)];
+        let mut q = Quaternion::identity();
+        for axis in e.order.axes() {
+            // Each rotation is about a world axis, so it goes on the left
+            q = Quaternion::from_axis_angle(units[axis], angles[axis]) * q;
+        }
+        q
+    }
+}
+impl From<Quaternion> for Matrix4 {
+    fn from(q: Quaternion) -> Matrix4 {
+        let Quaternion { x, y, z, w } = q.normalized();
+        Matrix4::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y), 0.0],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x), 0.0],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
     }
 }
 impl From<Vector> for Quaternion {
@@ -167,25 +198,99 @@ impl ops::Neg for Quaternion {
     }
 }
 
+impl Quaternion {
+    // Decomposes into angles applied in the given order. Near gimbal lock the middle angle is ±90° and
+    // only the sum or difference of the outer two is defined, so the last angle is taken as zero.
+    pub fn to_euler(self, order: RotationOrder) -> EulerRotation {
+        let m = Matrix4::from(self).m;
+        let [i, j, k] = order.axes();
+        let sign = if order.is_cyclic() { 1.0 } else { -1.0 };
+
+        let sin_middle = (-sign * m[k][i]).clamp(-1.0, 1.0);
+        let middle = sin_middle.asin();
+        let (first, last) = if sin_middle.abs() < 1.0 - 1e-6 {
+            ((sign * m[k][j]).atan2(m[k][k]), (sign * m[j][i]).atan2(m[i][i]))
+        } else {
+            ((-sign * m[j][k]).atan2(m[j][j]), 0.0)
+        };
+
+        let mut angles = [0.0; 3];
+        angles[i] = first;
+        angles[j] = middle;
+        angles[k] = last;
+        EulerRotation::new(angles[0], angles[1], angles[2]).with_order(order)
+    }
+}
+
 impl From<Quaternion> for EulerRotation {
     fn from(q: Quaternion) -> Self {
-        let q = q.normalized();
+        q.to_euler(RotationOrder::default())
+    }
+}
 
-        let sinr_cosp = 2.0 * (q.w * q.x + q.y * q.z);
-        let cosr_cosp = 1.0 - 2.0 * (q.x * q.x + q.y * q.y);
-        let roll = sinr_cosp.atan2(cosr_cosp);
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+    use crate::math::euler_rotation::EulerRotation;
+    use crate::math::rotation_order::RotationOrder;
+    use crate::math::vector::Vector;
+    use super::Quaternion;
 
-        let sinp = 2.0 * (q.w * q.y - q.z * q.x);
-        let pitch = if sinp.abs() >= 1.0 {
-            std::f32::consts::FRAC_PI_2.copysign(sinp)
-        } else {
-            sinp.asin()
-        };
+    const ORDERS: [RotationOrder; 6] = [RotationOrder::Xyz, RotationOrder::Xzy, RotationOrder::Yxz, RotationOrder::Yzx, RotationOrder::Zxy, RotationOrder::Zyx];
+
+    // Angles for an order with the given middle angle, and arbitrary first and last ones
+    fn euler(order: RotationOrder, middle: f32) -> EulerRotation {
+        let [first, second, last] = order.axes();
+        let mut angles = [0.0; 3];
+        angles[first] = 0.4;
+        angles[second] = middle;
+        angles[last] = -0.7;
+        EulerRotation::new(angles[0], angles[1], angles[2]).with_order(order)
+    }
 
-        let siny_cosp = 2.0 * (q.w * q.z + q.x * q.y);
-        let cosy_cosp = 1.0 - 2.0 * (q.y * q.y + q.z * q.z);
-        let yaw = siny_cosp.atan2(cosy_cosp);
+    // At gimbal lock the angles aren't unique, so the rotations are compared by where they take the axes
+    fn assert_same_rotation(a: Quaternion, b: Quaternion, context: &str) {
+        for axis in [Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 0.0, 1.0)] {
+            let (u, v) = (a * axis, b * axis);
+            assert!((u - v).len() < 1e-3, "{}: {} maps to {} and {}", context, axis, u, v);
+        }
+    }
+
+    #[test]
+    fn euler_round_trip_at_gimbal_lock() {
+        for order in ORDERS {
+            for middle in [FRAC_PI_2, -FRAC_PI_2] {
+                let q = Quaternion::from(euler(order, middle));
+                let back = q.to_euler(order);
+                assert_eq!(back.order, order);
+                assert_same_rotation(q, Quaternion::from(back), &format!("{:?} at {}", order, middle.to_degrees()));
+            }
+        }
+    }
 
-        EulerRotation::new(roll, pitch, yaw)
+    #[test]
+    fn euler_round_trip_near_gimbal_lock() {
+        for order in ORDERS {
+            for middle in [89.9_f32.to_radians(), -89.9_f32.to_radians()] {
+                let e = euler(order, middle);
+                let q = Quaternion::from(e);
+                let back = q.to_euler(order);
+                let context = format!("{:?} at {}", order, middle.to_degrees());
+                assert_same_rotation(q, Quaternion::from(back), &context);
+                let middle_axis = order.axes()[1];
+                assert!((back.angles()[middle_axis] - middle).abs() < 1e-3, "{}: middle angle came back as {}", context, back.angles()[middle_axis].to_degrees());
+            }
+        }
+    }
+
+    #[test]
+    fn euler_round_trip_away_from_gimbal_lock() {
+        for order in ORDERS {
+            let e = euler(order, 0.3);
+            let back = Quaternion::from(e).to_euler(order);
+            for (a, b) in e.angles().iter().zip(back.angles()) {
+                assert!((a - b).abs() < 1e-4, "{:?}: {:?} came back as {:?}", order, e.angles(), back.angles());
+            }
+        }
     }
 }
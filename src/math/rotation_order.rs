@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+// The order Euler angles are applied in, about the fixed world axes. Xyz rotates about x first and z last.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RotationOrder {
+    #[default]
+    Xyz,
+    Xzy,
+    Yxz,
+    Yzx,
+    Zxy,
+    Zyx,
+}
+
+impl RotationOrder {
+    // Axis indexes, first applied first
+    pub fn axes(&self) -> [usize; 3] {
+        match self {
+            RotationOrder::Xyz => [0, 1, 2],
+            RotationOrder::Xzy => [0, 2, 1],
+            RotationOrder::Yxz => [1, 0, 2],
+            RotationOrder::Yzx => [1, 2, 0],
+            RotationOrder::Zxy => [2, 0, 1],
+            RotationOrder::Zyx => [2, 1, 0],
+        }
+    }
+    fn from_axes(axes: [usize; 3]) -> Option<RotationOrder> {
+        [RotationOrder::Xyz, RotationOrder::Xzy, RotationOrder::Yxz, RotationOrder::Yzx, RotationOrder::Zxy, RotationOrder::Zyx]
+            .into_iter()
+            .find(|order| order.axes() == axes)
+    }
+    // Builds an order from the axis letters as written, e.g. "zx" in a command. Unnamed axes have no
+    // angle, so they go last in xyz order.
+    pub fn from_letters(letters: &str) -> Option<RotationOrder> {
+        let mut axes = Vec::with_capacity(3);
+        for c in letters.chars() {
+            let axis = match c {
+                'x' => 0,
+                'y' => 1,
+                'z' => 2,
+                _ => return None,
+            };
+            if axes.contains(&axis) {
+                return None;
+            }
+            axes.push(axis);
+        }
+        for axis in 0..3 {
+            if !axes.contains(&axis) {
+                axes.push(axis);
+            }
+        }
+        Self::from_axes([axes[0], axes[1], axes[2]])
+    }
+    // Whether the axes are an even permutation of xyz, which decides the signs when decomposing
+    pub fn is_cyclic(&self) -> bool {
+        matches!(self, RotationOrder::Xyz | RotationOrder::Yzx | RotationOrder::Zxy)
+    }
+}
+
+impl FromStr for RotationOrder {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.len() {
+            3 => Self::from_letters(&s.to_lowercase()).ok_or(format!("invalid rotation order '{}'", s)),
+            _ => Err(format!("invalid rotation order '{}'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RotationOrder;
+
+    #[test]
+    fn parses_every_order_in_any_case() {
+        for (text, order) in [("xyz", RotationOrder::Xyz), ("XZY", RotationOrder::Xzy), ("yXz", RotationOrder::Yxz), ("yzx", RotationOrder::Yzx), ("ZXY", RotationOrder::Zxy), ("zyx", RotationOrder::Zyx)] {
+            assert_eq!(text.parse::<RotationOrder>(), Ok(order));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_orders() {
+        for text in ["", "xy", "xyzx", "xxy", "abc", "x-z", "xyz "] {
+            assert!(text.parse::<RotationOrder>().is_err(), "'{}' parsed", text);
+        }
+    }
+
+    #[test]
+    fn letters_fill_in_missing_axes() {
+        assert_eq!(RotationOrder::from_letters("zx"), Some(RotationOrder::Zxy));
+        assert_eq!(RotationOrder::from_letters("y"), Some(RotationOrder::Yxz));
+        assert_eq!(RotationOrder::from_letters("xx"), None);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+use crate::math::quaternion::Quaternion;
+use crate::math::rotation::Rotation;
+use crate::math::vector::Vector;
+use crate::math::vertex::Vertex;
+
+// Scales, then rotates around the pivot, then translates
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub translation: Vector,
+    pub rotation: Quaternion,
+    pub scale: Vector,
+    pub pivot: Vector,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            translation: Vector::zero(),
+            rotation: Quaternion::identity(),
+            scale: Vector::new(1.0, 1.0, 1.0),
+            pivot: Vector::zero(),
+        }
+    }
+    pub fn apply_vertex(&self, v: Vertex) -> Vertex {
+        let local = (Vector::from(v) - self.pivot).mul_components(self.scale);
+        (self.rotation.rotate_vector(local) + self.pivot + self.translation).into()
+    }
+    // The same mapping expressed around a different pivot
+    pub fn with_pivot(&self, pivot: Vector) -> Transform {
+        let origin: Vector = self.apply_vertex(Vertex::zero()).into();
+        let moved = self.rotation * pivot.mul_components(self.scale) - pivot;
+        Transform { translation: origin + moved, pivot, ..*self }
+    }
+    // self applied after child. Exact as long as self has a uniform scale; otherwise the shear a
+    // rotated child would pick up is dropped.
+    pub fn compose(&self, child: &Transform) -> Transform {
+        let child_origin: Vector = child.apply_vertex(Vertex::zero()).into();
+        Transform {
+            translation: self.apply_vertex(child_origin.into()).into(),
+            rotation: (self.rotation * child.rotation).normalized(),
+            scale: self.scale.mul_components(child.scale),
+            pivot: Vector::zero(),
+        }
+    }
+    // The local transform that gives self once composed under parent
+    pub fn relative_to(&self, parent: &Transform) -> Transform {
+        let inverse = parent.rotation.normalized().conjugate();
+        let origin: Vector = self.apply_vertex(Vertex::zero()).into();
+        let parent_origin: Vector = parent.apply_vertex(Vertex::zero()).into();
+        let local = Transform {
+            translation: (inverse * (origin - parent_origin)).div_components(parent.scale),
+            rotation: (inverse * self.rotation).normalized(),
+            scale: self.scale.div_components(parent.scale),
+            pivot: Vector::zero(),
+        };
+        local.with_pivot(self.pivot)
+    }
+}
+
+impl Display for Transform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pos {} rot {} scale {}", self.translation, self.rotation, self.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::matrix::Matrix4;
+    use crate::math::quaternion::Quaternion;
+    use crate::math::vector::Vector;
+    use crate::math::vertex::Vertex;
+    use super::Transform;
+
+    fn transform(translation: Vector, angle: f32, scale: Vector, pivot: Vector) -> Transform {
+        let rotation = Quaternion::from_axis_angle(Vector::new(1.0, 2.0, 3.0).normalized(), angle);
+        Transform { translation, rotation, scale, pivot }
+    }
+
+    fn samples() -> Vec<Vertex> {
+        vec![Vertex::new(0.0, 0.0, 0.0), Vertex::new(1.0, -2.0, 3.0), Vertex::new(-4.0, 0.5, 2.0)]
+    }
+
+    fn close(a: Vertex, b: Vertex) -> bool {
+        (Vector::from(a) - Vector::from(b)).len() < 1e-4
+    }
+
+    #[test]
+    fn scales_then_rotates_about_the_pivot_then_translates() {
+        // A quarter turn about z around (1, 0, 0), after doubling x
+        let t = Transform {
+            translation: Vector::new(0.0, 0.0, 5.0),
+            rotation: Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2),
+            scale: Vector::new(2.0, 1.0, 1.0),
+            pivot: Vector::new(1.0, 0.0, 0.0),
+        };
+        assert!(close(t.apply_vertex(Vertex::new(2.0, 0.0, 0.0)), Vertex::new(1.0, 2.0, 5.0)));
+        // The pivot itself only moves with the translation
+        assert!(close(t.apply_vertex(Vertex::new(1.0, 0.0, 0.0)), Vertex::new(1.0, 0.0, 5.0)));
+    }
+
+    #[test]
+    fn matrix_matches_the_transform() {
+        let t = transform(Vector::new(3.0, -1.0, 2.0), 0.8, Vector::new(1.0, 2.0, 0.5), Vector::new(0.5, 0.5, -1.0));
+        let m = Matrix4::from_transform(&t);
+        for v in samples() {
+            assert!(close(m.transform_vertex(v), t.apply_vertex(v)));
+        }
+    }
+
+    #[test]
+    fn moving_the_pivot_keeps_the_mapping() {
+        let t = transform(Vector::new(3.0, -1.0, 2.0), 0.8, Vector::new(1.0, 2.0, 0.5), Vector::zero());
+        let moved = t.with_pivot(Vector::new(4.0, 0.0, -2.0));
+        for v in samples() {
+            assert!(close(moved.apply_vertex(v), t.apply_vertex(v)));
+        }
+    }
+
+    #[test]
+    fn compose_and_relative_to_undo_each_other() {
+        let parent = transform(Vector::new(10.0, 0.0, 0.0), 1.1, Vector::new(2.0, 2.0, 2.0), Vector::new(1.0, 0.0, 0.0));
+        let child = transform(Vector::new(0.0, 3.0, -1.0), -0.4, Vector::new(1.0, 0.5, 3.0), Vector::new(0.0, 1.0, 0.0));
+        let world = parent.compose(&child);
+        for v in samples() {
+            assert!(close(world.apply_vertex(v), parent.apply_vertex(child.apply_vertex(v))));
+        }
+        let local = world.relative_to(&parent);
+        for v in samples() {
+            assert!(close(local.apply_vertex(v), child.apply_vertex(v)));
+        }
+    }
+}
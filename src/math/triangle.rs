@@ -1,5 +1,4 @@
 use std::ops;
-use crate::math::rotation::Rotation;
 use crate::math::vector::Vector;
 use crate::math::vertex::Vertex;
 use crate::rendering::stroke::Stroke;
@@ -17,17 +16,6 @@ impl Triangle {
     pub fn from_array(p: [Vertex; 3], s: Stroke) -> Self {
         Self { verts: p, stroke: s }
     }
-    pub fn avg_z(&self) -> f32 {
-        return self.verts.iter().map(|v| v.z).sum::<f32>() / self.verts.len() as f32;
-    }
-    pub fn translate(&self, v: &Vector) -> Triangle {
-        let vs = self.verts.map(|vtx| vtx + *v);
-        Self::from_array(vs, self.stroke)
-    }
-    pub fn rotate<R: Rotation>(&self, r: &R) -> Triangle {
-        let vs = self.verts.map(|v| r.rotate_vertex(v));
-        Self::from_array(vs, self.stroke)
-    }
     pub fn with_stroke(&self, stroke: Stroke) -> Triangle {
         Self { verts: self.verts, stroke }
     }
@@ -1,4 +1,5 @@
-use std::ops::{AddAssign, Div, Mul, MulAssign};
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub};
 use crate::math::vertex::Vertex;
 
 #[derive(Debug, Copy, Clone)]
@@ -24,6 +25,12 @@ impl Vector {
     pub fn normalized(&self) -> Vector {
         *self / self.len()
     }
+    pub fn mul_components(self, other: Vector) -> Vector {
+        Self::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+    pub fn div_components(self, other: Vector) -> Vector {
+        Self::new(self.x / other.x, self.y / other.y, self.z / other.z)
+    }
     pub fn cross(self, other: Vector) -> Vector {
         Self::new(
             self.y * other.z - self.z * other.y,
@@ -45,6 +52,31 @@ impl From<Vertex> for Vector {
     }
 }
 
+impl Display for Vector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({:.2}, {:.2}, {:.2})", self.x, self.y, self.z)
+    }
+}
+
+impl Add<Vector> for Vector {
+    type Output = Vector;
+    fn add(self, rhs: Vector) -> Vector {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+impl Sub<Vector> for Vector {
+    type Output = Vector;
+    fn sub(self, rhs: Vector) -> Vector {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+impl Neg for Vector {
+    type Output = Vector;
+    fn neg(self) -> Vector {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
 impl Mul<f32> for Vector {
     type Output = Vector;
     fn mul(self, rhs: f32) -> Vector {
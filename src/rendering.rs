@@ -4,4 +4,48 @@ pub mod point;
 pub mod stroke;
 pub mod camera;
 pub mod render_buffer;
-pub mod object;
\ No newline at end of file
+pub mod object;
+pub mod shading_mode;
+pub mod render_settings;
+
+pub mod clipping;
+pub mod render_mode;
+pub mod line_style;
+pub mod outline;
+pub mod texture;
+pub mod texture_filter;
+pub mod varyings;
+pub mod render_target;
+pub mod sample_buffer;
+pub mod fog;
+pub mod directional_light;
+pub mod shadows;
+pub mod shadow_map;
+pub mod transparency;
+pub mod weighted_blend;
+pub mod skybox;
+pub mod background;
+pub mod text_style;
+pub mod fill_mode;
+pub mod post_process;
+pub mod edge_detect;
+pub mod posterize;
+pub mod exposure;
+pub mod sharpen;
+pub mod scanlines;
+pub mod color_lut;
+pub mod fragment_input;
+pub mod shader;
+pub mod flat_shader;
+pub mod lambert_shader;
+pub mod blinn_phong_shader;
+pub mod toon_shader;
+pub mod normal_shader;
+pub mod depth_shader;
+pub mod frame_context;
+pub mod uv_shader;
+pub mod object_id_shader;
+pub mod pulse_shader;
+pub mod debug_view;
+pub mod axis_gizmo;
+pub mod grid;
\ No newline at end of file
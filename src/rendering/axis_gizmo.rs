@@ -0,0 +1,85 @@
+use crate::math::matrix::Matrix4;
+use crate::math::vector::Vector;
+use crate::rendering::point::Point;
+use crate::rendering::rasterizer::{draw_line, ScreenLine, ScreenVertex};
+use crate::rendering::render_settings::RenderSettings;
+use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::stroke::Stroke;
+use crate::rendering::varyings::Varyings;
+
+// x, y and z, also used for the grid lines along the world axes
+pub const AXIS_COLORS: [[u8; 3]; 3] = [[255, 80, 80], [80, 255, 80], [80, 120, 255]];
+const AXIS_LABELS: [char; 3] = ['x', 'y', 'z'];
+
+// The world axes as seen by the camera, drawn in the bottom left corner over everything else
+#[derive(Debug, Clone, Copy)]
+pub struct AxisGizmo {
+    // Length of an axis pointing across the screen, in cells
+    pub size: u16,
+}
+
+impl AxisGizmo {
+    pub fn new(size: u16) -> Self {
+        Self { size }
+    }
+    pub fn draw(&self, buf: &mut ScreenBuffer, view: &Matrix4, settings: &RenderSettings) {
+        let size = self.size as f32;
+        // Cells are about twice as tall as they are wide
+        let origin = Point::new(size + 1.0, buf.height as f32 - size / 2.0 - 2.0);
+        let mut axes: Vec<(usize, Vector)> = [Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 0.0, 1.0)]
+            .into_iter()
+            .map(|axis| view.transform_vector(axis))
+            .enumerate()
+            .collect();
+        // Axes pointing away from the camera first, so the ones pointing towards it end up on top
+        axes.sort_by(|a, b| b.1.z.total_cmp(&a.1.z));
+
+        for (i, axis) in axes {
+            let tip = Point::new(origin.x + axis.x * size, origin.y - axis.y * size / 2.0);
+            // In front of everything, so the depth test always passes
+            let vertex = |point| ScreenVertex { point, depth: 0.0, inv_w: 1.0, varyings: Varyings::new(Vector::zero(), Vector::zero(), [0.0; 2], 0.0) };
+            draw_line(buf, &ScreenLine { verts: [vertex(origin), vertex(tip)], stroke: Stroke::new(AXIS_COLORS[i], ' ') }, settings);
+
+            let label = Point::new(origin.x + axis.x * (size + 1.0), origin.y - axis.y * (size + 1.0) / 2.0);
+            let (x, y) = (label.x.floor(), label.y.floor());
+            if x >= 0.0 && y >= 0.0 && x < buf.width as f32 && y < buf.height as f32 {
+                buf.set_pixel(x as u16, y as u16, Stroke::new(AXIS_COLORS[i], AXIS_LABELS[i]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::matrix::Matrix4;
+    use crate::math::quaternion::Quaternion;
+    use crate::math::vector::Vector;
+    use crate::rendering::render_settings::RenderSettings;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use super::AxisGizmo;
+
+    // Where each label ends up
+    fn labels(view: &Matrix4) -> Vec<(char, u16, u16)> {
+        let mut buf = ScreenBuffer::new(20, 12);
+        AxisGizmo::new(4).draw(&mut buf, view, &RenderSettings::new());
+        let mut found = Vec::new();
+        for y in 0..buf.height {
+            for x in 0..buf.width {
+                let tex = buf.get_pixel(x, y).tex;
+                if ['x', 'y'].contains(&tex) {
+                    found.push((tex, x, y));
+                }
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn axes_turn_with_the_view() {
+        // From the bottom left corner: x to the right and y up
+        assert_eq!(labels(&Matrix4::identity()), [('y', 5, 5), ('x', 10, 8)]);
+        // Turned around, x points left
+        let behind = Matrix4::from_quaternion(Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), std::f32::consts::PI));
+        assert_eq!(labels(&behind), [('y', 5, 5), ('x', 0, 8)]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::rc::Rc;
+use crate::math::projection_type::ProjectionType;
+use crate::math::vector::Vector;
+use crate::rendering::camera::Camera;
+use crate::rendering::point::Point;
+use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::skybox::Skybox;
+use crate::rendering::texture_filter::TextureFilter;
+
+// What's behind the scene, written as cell background colours
+#[derive(Debug, Clone)]
+pub enum Background {
+    Solid([u8; 3]),
+    // Blends from the horizon up to straight overhead, and stays the horizon colour below it
+    Gradient { horizon: [u8; 3], zenith: [u8; 3] },
+    Skybox(Rc<Skybox>),
+}
+
+impl Background {
+    // The colour seen looking along a world space direction
+    pub fn color(&self, direction: Vector, filter: TextureFilter) -> [u8; 3] {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { horizon, zenith } => {
+                let t = direction.normalized().y.max(0.0);
+                let mut color = *horizon;
+                for (c, z) in color.iter_mut().zip(zenith) {
+                    *c = (*c as f32 + (*z as f32 - *c as f32) * t).round() as u8;
+                }
+                color
+            }
+            Background::Skybox(skybox) => skybox.sample(direction, filter).map(|c| c.round().clamp(0.0, 255.0) as u8),
+        }
+    }
+    // The colour at the horizon straight ahead, which fog fades toward
+    pub fn horizon(&self, forward: Vector, filter: TextureFilter) -> [u8; 3] {
+        let level = Vector::new(forward.x, 0.0, forward.z);
+        let level = if level.len() > 1e-6 { level } else { Vector::new(0.0, 0.0, 1.0) };
+        self.color(level, filter)
+    }
+    // Fills every cell with the colour along the ray through its centre
+    pub fn draw(&self, buf: &mut ScreenBuffer, camera: &Camera, prj_type: &ProjectionType, filter: TextureFilter) {
+        if let Background::Solid(color) = self {
+            for y in 0..buf.height {
+                for x in 0..buf.width {
+                    buf.set_background(x, y, *color);
+                }
+            }
+            return;
+        }
+        let Some(inverse) = camera.view_projection(prj_type).inverse() else {
+            return;
+        };
+        let unproject = |ndc_x: f32, ndc_y: f32, depth: f32| {
+            let [x, y, z, w] = inverse.transform_point([ndc_x, ndc_y, depth, 1.0]);
+            Vector::new(x / w, y / w, z / w)
+        };
+        for y in 0..buf.height {
+            for x in 0..buf.width {
+                let (ndc_x, ndc_y) = camera.ndc(Point::new(x as f32 + 0.5, y as f32 + 0.5));
+                let direction = unproject(ndc_x, ndc_y, 1.0) - unproject(ndc_x, ndc_y, 0.0);
+                buf.set_background(x, y, self.color(direction, filter));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::projection_type::ProjectionType;
+    use crate::math::vector::Vector;
+    use crate::math::vertex::Vertex;
+    use crate::rendering::camera::Camera;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use crate::rendering::texture_filter::TextureFilter;
+    use super::Background;
+
+    const SKY: Background = Background::Gradient { horizon: [200, 100, 0], zenith: [0, 100, 200] };
+
+    #[test]
+    fn gradient_runs_from_horizon_to_zenith() {
+        let color = |direction: Vector| SKY.color(direction, TextureFilter::Nearest);
+        assert_eq!(color(Vector::new(1.0, 0.0, 0.0)), [200, 100, 0]);
+        assert_eq!(color(Vector::new(0.0, 5.0, 0.0)), [0, 100, 200]);
+        assert_eq!(color(Vector::new(0.0, 1.0, 1.0)), [59, 100, 141]);
+        // Below the horizon it stays the horizon colour
+        assert_eq!(color(Vector::new(0.0, -1.0, 0.0)), [200, 100, 0]);
+        // Fog looks level, even with the camera pointing straight up
+        assert_eq!(SKY.horizon(Vector::new(0.0, 1.0, 0.0), TextureFilter::Nearest), [200, 100, 0]);
+    }
+
+    #[test]
+    fn draw_colours_cells_by_their_view_ray() {
+        let camera = Camera::new(Vertex::new(0.0, 0.0, 0.0), 8, 8);
+        let mut buf = ScreenBuffer::new(8, 8);
+        SKY.draw(&mut buf, &camera, &ProjectionType::Perspective, TextureFilter::Nearest);
+        // Looking level, the top half is sky and the bottom half horizon
+        assert!(buf.background_at(4, 0)[2] > buf.background_at(4, 3)[2]);
+        assert!(buf.background_at(4, 3)[2] > 0);
+        assert_eq!(buf.background_at(4, 7), [200, 100, 0]);
+
+        Background::Solid([1, 2, 3]).draw(&mut buf, &camera, &ProjectionType::Perspective, TextureFilter::Nearest);
+        assert_eq!(buf.background_at(0, 0), [1, 2, 3]);
+    }
+}
@@ -0,0 +1,42 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Ambient plus Lambert diffuse in the face's colour, with white highlights where the normal lines up
+// with the half vector between the light and the eye
+#[derive(Debug, Clone, Copy)]
+pub struct BlinnPhongShader {
+    pub ambient: f32,
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl BlinnPhongShader {
+    pub fn new(ambient: f32, specular: f32, shininess: f32) -> Self {
+        Self { ambient, specular, shininess }
+    }
+}
+
+impl Shader for BlinnPhongShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        if input.lights.is_empty() {
+            return input.base;
+        }
+        let normal = input.normal.normalized();
+        let to_eye = (input.eye - input.position).normalized();
+        let (mut diffuse, mut highlight) = (0.0, 0.0);
+        for light in input.lights {
+            let to_light = -light.direction;
+            let cos = normal.dot(to_light);
+            diffuse += cos.max(0.0);
+            if cos > 0.0 {
+                let half = (to_light + to_eye).normalized();
+                highlight += normal.dot(half).max(0.0).powf(self.shininess);
+            }
+        }
+        let count = input.lights.len() as f32;
+        let diffuse = self.ambient + (1.0 - self.ambient) * diffuse / count;
+        let highlight = 255.0 * self.specular * highlight / count;
+        input.base.with_color(input.base.color.map(|c| (c as f32 * diffuse + highlight).round().clamp(0.0, 255.0) as u8))
+    }
+}
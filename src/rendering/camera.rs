@@ -1,44 +1,69 @@
 use crate::debug::debug_logger::log;
+use crate::math::matrix::Matrix4;
+use crate::math::quaternion::Quaternion;
 use crate::math::triangle::Triangle;
 use crate::math::vector::Vector;
 use crate::math::vertex::Vertex;
 use crate::math::projection_type::ProjectionType;
 use crate::rendering::point::Point;
 
+// Screen cells per world unit at unit distance with a 90° fov
 pub const SCALE: f32 = 20.0;
+pub const NEAR: f32 = 0.1;
+pub const FAR: f32 = 1000.0;
+
 pub struct Camera {
     pub origin: Vertex,
+    pub rotation: Quaternion,
     screen_width: u16,
     screen_height: u16,
     fov: f32
 }
 impl Camera {
     pub fn new(origin: Vertex, screen_width: u16, screen_height: u16) -> Self {
-        Self { origin, screen_width, screen_height, fov: 90.0_f32.to_radians() }
+        Self { origin, rotation: Quaternion::identity(), screen_width, screen_height, fov: 90.0_f32.to_radians() }
     }
     pub fn mv(&mut self, v: Vector) {
         self.origin = self.origin + v;
     }
-    pub fn project(&self, v: Vertex, prj_type: &ProjectionType) -> Point {
-        let center_x = self.screen_width as f32 / 2.0;
+    // Yaw turns around the world's up axis so the horizon stays level; pitch and roll are local
+    pub fn rotate(&mut self, euler: Vector) {
+        let yaw = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), euler.y);
+        let pitch = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), euler.x);
+        let roll = Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), euler.z);
+        self.rotation = (yaw * self.rotation * pitch * roll).normalized();
+    }
+    pub fn view_matrix(&self) -> Matrix4 {
+        let rotation = Matrix4::from_quaternion(self.rotation.conjugate());
+        rotation * Matrix4::from_translation(-Vector::from(self.origin))
+    }
+    // Terminal cells are about twice as tall as they are wide, hence the halved aspect ratio
+    pub fn projection_matrix(&self, prj_type: &ProjectionType) -> Matrix4 {
         let center_y = self.screen_height as f32 / 2.0;
-        let v = v - self.origin;
-        
-        let p = match prj_type {
+        let aspect = self.screen_width as f32 / (2.0 * self.screen_height as f32);
+        match prj_type {
             ProjectionType::Perspective => {
-                let f = 1.0 / (self.fov / 2.0).tan();
-                (v.x * f * SCALE / v.z,
-                v.y * f * SCALE / -v.z)
+                let f = SCALE / ((self.fov / 2.0).tan() * center_y);
+                Matrix4::perspective(2.0 * (1.0 / f).atan(), aspect, NEAR, FAR)
             }
             ProjectionType::Orthographic => {
-                (v.x,
-                v.y)
+                Matrix4::orthographic(self.screen_width as f32 / 2.0, self.screen_height as f32, -FAR, FAR)
             }
-        };
-
-        Point::new(
-            (p.0 * 2.0 + center_x).round(),
-            (p.1 + center_y).round()
-        )
+        }
+    }
+    pub fn view_projection(&self, prj_type: &ProjectionType) -> Matrix4 {
+        self.projection_matrix(prj_type) * self.view_matrix()
     }
-}
\ No newline at end of file
+    // Maps normalized device coordinates to screen cells
+    pub fn viewport(&self, ndc_x: f32, ndc_y: f32) -> Point {
+        let center_x = self.screen_width as f32 / 2.0;
+        let center_y = self.screen_height as f32 / 2.0;
+        Point::new(center_x + ndc_x * center_x, center_y - ndc_y * center_y)
+    }
+    // The inverse of viewport
+    pub fn ndc(&self, p: Point) -> (f32, f32) {
+        let center_x = self.screen_width as f32 / 2.0;
+        let center_y = self.screen_height as f32 / 2.0;
+        ((p.x - center_x) / center_x, (center_y - p.y) / center_y)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::rendering::varyings::Varyings;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClipVertex {
+    pub pos: [f32; 4],
+    pub varyings: Varyings,
+}
+
+impl ClipVertex {
+    pub fn new(pos: [f32; 4], varyings: Varyings) -> Self {
+        Self { pos, varyings }
+    }
+    pub fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        let mut pos = [0.0; 4];
+        for (i, p) in pos.iter_mut().enumerate() {
+            *p = self.pos[i] + (other.pos[i] - self.pos[i]) * t;
+        }
+        // Still before the perspective divide, so plain linear interpolation is correct here
+        ClipVertex::new(pos, self.varyings.lerp(&other.varyings, t))
+    }
+}
+
+// The view volume in clip space: -w <= x <= w, -w <= y <= w, 0 <= z <= w
+const PLANES: [[f32; 4]; 6] = [
+    [1.0, 0.0, 0.0, 1.0],
+    [-1.0, 0.0, 0.0, 1.0],
+    [0.0, 1.0, 0.0, 1.0],
+    [0.0, -1.0, 0.0, 1.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, -1.0, 1.0],
+];
+
+fn distance(plane: &[f32; 4], v: &ClipVertex) -> f32 {
+    (0..4).map(|i| plane[i] * v.pos[i]).sum()
+}
+
+// Sutherland-Hodgman against every plane of the view volume. The result is a convex polygon,
+// empty if the triangle is entirely outside.
+pub fn clip_triangle(tri: [ClipVertex; 3]) -> Vec<ClipVertex> {
+    let mut polygon = tri.to_vec();
+    if tri.iter().all(|v| PLANES.iter().all(|p| distance(p, v) >= 0.0)) {
+        return polygon;
+    }
+
+    for plane in PLANES.iter() {
+        let input = std::mem::take(&mut polygon);
+        for (i, current) in input.iter().enumerate() {
+            let next = &input[(i + 1) % input.len()];
+            let d_current = distance(plane, current);
+            let d_next = distance(plane, next);
+
+            if d_current >= 0.0 {
+                polygon.push(*current);
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                polygon.push(current.lerp(next, d_current / (d_current - d_next)));
+            }
+        }
+        if polygon.is_empty() {
+            break;
+        }
+    }
+    polygon
+}
+
+
+// Liang-Barsky against the same planes, None if the segment is entirely outside
+pub fn clip_line(a: ClipVertex, b: ClipVertex) -> Option<(ClipVertex, ClipVertex)> {
+    let (mut t0, mut t1) = (0.0_f32, 1.0_f32);
+    for plane in PLANES.iter() {
+        let d_a = distance(plane, &a);
+        let d_b = distance(plane, &b);
+        if d_a < 0.0 && d_b < 0.0 {
+            return None;
+        }
+        if d_a < 0.0 {
+            t0 = t0.max(d_a / (d_a - d_b));
+        }
+        else if d_b < 0.0 {
+            t1 = t1.min(d_a / (d_a - d_b));
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    Some((a.lerp(&b, t0), a.lerp(&b, t1)))
+}
\ No newline at end of file
@@ -0,0 +1,143 @@
+use std::fs;
+use std::path::Path;
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::screen_buffer::ScreenBuffer;
+
+// Colour grading through a 3D lookup table in the .cube format, interpolated trilinearly
+#[derive(Debug, Clone)]
+pub struct ColorLut {
+    size: usize,
+    // Red varies fastest, then green, then blue
+    table: Vec<[f32; 3]>,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+}
+
+impl ColorLut {
+    pub fn load(path: &Path) -> Result<ColorLut, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_cube(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    pub fn from_cube(text: &str) -> Result<ColorLut, String> {
+        let mut size = None;
+        let mut table = Vec::new();
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let triple = |tokens: &[&str]| -> Result<[f32; 3], String> {
+            let values: Vec<f32> = tokens.iter()
+                .map(|t| t.parse::<f32>().map_err(|_| format!("invalid number '{}'", t)))
+                .collect::<Result<_, _>>()?;
+            <[f32; 3]>::try_from(values).map_err(|_| "expected three values".to_string())
+        };
+
+        for line in text.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                [] => {}
+                [first, ..] if first.starts_with('#') => {}
+                ["TITLE", ..] => {}
+                ["LUT_3D_SIZE", n] => size = Some(n.parse::<usize>().map_err(|_| format!("invalid size '{}'", n))?),
+                ["LUT_1D_SIZE", ..] => return Err("1D LUTs aren't supported".to_string()),
+                ["DOMAIN_MIN", rest @ ..] => domain_min = triple(rest)?,
+                ["DOMAIN_MAX", rest @ ..] => domain_max = triple(rest)?,
+                values => table.push(triple(values)?),
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        // The .cube format allows up to 256 entries per axis
+        if !(2..=256).contains(&size) {
+            return Err(format!("LUT_3D_SIZE must be between 2 and 256, got {}", size));
+        }
+        // Colours are divided by the width of the domain
+        let finite = domain_min.iter().chain(&domain_max).all(|v| v.is_finite());
+        if !finite || (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err(format!("DOMAIN_MAX {:?} must be above DOMAIN_MIN {:?} on every axis", domain_max, domain_min));
+        }
+        if table.len() != size * size * size {
+            return Err(format!("expected {} entries for size {}, found {}", size * size * size, size, table.len()));
+        }
+        Ok(ColorLut { size, table, domain_min, domain_max })
+    }
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[(b * self.size + g) * self.size + r]
+    }
+    pub fn lookup(&self, color: [u8; 3]) -> [u8; 3] {
+        let max = (self.size - 1) as f32;
+        // Position in the table along each axis, split into a cell and how far into it
+        let mut cell = [0; 3];
+        let mut frac = [0.0; 3];
+        for i in 0..3 {
+            let t = (color[i] as f32 / 255.0 - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i]);
+            let p = t.clamp(0.0, 1.0) * max;
+            cell[i] = (p.floor() as usize).min(self.size - 2);
+            frac[i] = p - cell[i] as f32;
+        }
+
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let mut weight = 1.0;
+            for i in 0..3 {
+                weight *= if offset[i] == 1 { frac[i] } else { 1.0 - frac[i] };
+            }
+            let value = self.entry(cell[0] + offset[0], cell[1] + offset[1], cell[2] + offset[2]);
+            for i in 0..3 {
+                out[i] += value[i] * weight;
+            }
+        }
+        out.map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8)
+    }
+}
+
+impl PostProcess for ColorLut {
+    fn apply(&self, buf: &mut ScreenBuffer) {
+        buf.map_colors(|color| self.lookup(color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColorLut;
+
+    // A table that maps every colour to itself, with any extra header lines
+    fn identity_cube(header: &str) -> String {
+        let mut text = format!("TITLE \"identity\"\n{}\nLUT_3D_SIZE 2\n", header);
+        for i in 0..8 {
+            text += &format!("{} {} {}\n", i & 1, (i >> 1) & 1, (i >> 2) & 1);
+        }
+        text
+    }
+
+    #[test]
+    fn identity_table_keeps_colours() {
+        let lut = ColorLut::from_cube(&identity_cube("# comment")).unwrap();
+        for color in [[0, 0, 0], [255, 255, 255], [12, 128, 200]] {
+            assert_eq!(lut.lookup(color), color);
+        }
+    }
+
+    #[test]
+    fn domain_stretches_the_input() {
+        let lut = ColorLut::from_cube(&identity_cube("DOMAIN_MIN 0 0 0\nDOMAIN_MAX 0.5 0.5 0.5")).unwrap();
+        assert_eq!(lut.lookup([64, 0, 255]), [128, 0, 255]);
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        let bad = [
+            identity_cube("DOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 0 1"),
+            identity_cube("DOMAIN_MIN 0 0.5 0\nDOMAIN_MAX 1 0.2 1"),
+            identity_cube("DOMAIN_MAX 1 1"),
+            identity_cube("DOMAIN_MAX 1 nan 1"),
+            identity_cube("").replace("LUT_3D_SIZE 2", "LUT_3D_SIZE 1"),
+            identity_cube("").replace("LUT_3D_SIZE 2", "LUT_3D_SIZE 257"),
+            identity_cube("").replace("LUT_3D_SIZE 2\n", ""),
+            identity_cube("").replace("1 1 1\n", ""),
+            identity_cube("0 0 0"),
+        ];
+        for text in &bad {
+            assert!(ColorLut::from_cube(text).is_err(), "accepted {:?}", text);
+        }
+    }
+}
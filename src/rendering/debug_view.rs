@@ -0,0 +1,158 @@
+use crate::math::geometry::signed_area;
+use crate::rendering::normal_shader::NormalShader;
+use crate::rendering::object_id_shader::ObjectIdShader;
+use crate::rendering::point::Point;
+use crate::rendering::rasterizer::{rasterize, ScreenTriangle};
+use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Colours cells by what went into them rather than how they're lit, to find out why a model renders wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+    Normals,
+    // Distance from the camera, stretched over the nearest and farthest surfaces on screen
+    Depth,
+    // How many triangles covered each cell, whether or not they ended up in front
+    Overdraw,
+    ObjectId,
+}
+
+impl DebugView {
+    // Steps through the views and back to none, for a key that cycles them
+    pub fn next(view: Option<DebugView>) -> Option<DebugView> {
+        match view {
+            None => Some(DebugView::Normals),
+            Some(DebugView::Normals) => Some(DebugView::Depth),
+            Some(DebugView::Depth) => Some(DebugView::Overdraw),
+            Some(DebugView::Overdraw) => Some(DebugView::ObjectId),
+            Some(DebugView::ObjectId) => None,
+        }
+    }
+    // The shader that stands in for every object's own, for views that are drawn with one
+    pub fn shader(&self) -> Option<&'static dyn Shader> {
+        match self {
+            DebugView::Normals => Some(&NormalShader),
+            DebugView::ObjectId => Some(&ObjectIdShader),
+            DebugView::Depth | DebugView::Overdraw => None,
+        }
+    }
+}
+
+// Recolours every drawn cell in grey by its depth, white for the nearest and black for the farthest
+pub fn draw_depth(buf: &mut ScreenBuffer) {
+    let mut range = (f32::INFINITY, f32::NEG_INFINITY);
+    for y in 0..buf.height {
+        for x in 0..buf.width {
+            let depth = buf.depth_at(x, y);
+            if depth.is_finite() {
+                range = (range.0.min(depth), range.1.max(depth));
+            }
+        }
+    }
+    let (near, far) = range;
+    for y in 0..buf.height {
+        for x in 0..buf.width {
+            let depth = buf.depth_at(x, y);
+            if !depth.is_finite() {
+                continue;
+            }
+            let t = if far > near { (depth - near) / (far - near) } else { 0.0 };
+            let grey = ((1.0 - t) * 255.0).round() as u8;
+            buf.set_pixel(x, y, Stroke::filled([grey; 3]));
+        }
+    }
+}
+
+// Writes each cell's triangle count as a digit, coloured from blue for one to red for eight or more
+pub fn draw_overdraw(buf: &mut ScreenBuffer, tris: &[ScreenTriangle]) {
+    let points: Vec<[Point; 3]> = tris.iter().map(|tri| tri.verts.map(|v| v.point)).collect();
+    let counts = overdraw_counts(buf.width, buf.height, &points);
+    for y in 0..buf.height {
+        for x in 0..buf.width {
+            let count = counts[y as usize * buf.width as usize + x as usize];
+            if count == 0 {
+                continue;
+            }
+            let t = ((count - 1) as f32 / 7.0).min(1.0);
+            let color = if t < 0.5 {
+                [0.0, t * 2.0, 1.0 - t * 2.0]
+            } else {
+                [(t - 0.5) * 2.0, 1.0 - (t - 0.5) * 2.0, 0.0]
+            };
+            let tex = char::from_digit(count, 10).unwrap_or('+');
+            buf.set_pixel(x, y, Stroke::new(color.map(|c| (c * 255.0).round() as u8), tex));
+        }
+    }
+}
+
+// How many triangles cover each cell, row by row. Both windings count, since back faces are drawn too
+// when culling is off.
+fn overdraw_counts(width: u16, height: u16, tris: &[[Point; 3]]) -> Vec<u32> {
+    let mut counts = vec![0u32; width as usize * height as usize];
+    for tri in tris {
+        let mut points = *tri;
+        if signed_area(points[0], points[1], points[2]) < 0.0 {
+            points.swap(1, 2);
+        }
+        rasterize(width, height, points, |x, y, _| {
+            counts[y as usize * width as usize + x as usize] += 1;
+        });
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::point::Point;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use crate::rendering::stroke::Stroke;
+    use super::{draw_depth, overdraw_counts, DebugView};
+
+    #[test]
+    fn overlapping_triangles_count_in_either_winding() {
+        // Two halves of the same square, one wound each way, and a third over the left half
+        let tris = [
+            [Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(0.0, 4.0)],
+            [Point::new(4.0, 0.0), Point::new(0.0, 4.0), Point::new(4.0, 4.0)],
+            [Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 4.0)],
+        ];
+        let reversed = tris.map(|[a, b, c]| [a, c, b]);
+        for tris in [tris, reversed] {
+            let counts = overdraw_counts(4, 4, &tris);
+            let at = |x: usize, y: usize| counts[y * 4 + x];
+            // The two halves tile the square, so every cell is covered by exactly one of them
+            assert_eq!(at(3, 0), 1);
+            assert_eq!(at(3, 3), 1);
+            assert_eq!(at(0, 3), 1);
+            assert_eq!(at(1, 0), 2);
+            assert!(counts.iter().all(|c| (1..=2).contains(c)), "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn depth_view_stretches_over_what_is_on_screen() {
+        let mut buf = ScreenBuffer::new(4, 1);
+        for (x, depth) in [(0, 0.2), (1, 0.4), (2, 0.6)] {
+            buf.test_and_set_depth(x, 0, depth);
+            buf.set_pixel(x, 0, Stroke::new([10, 200, 30], '#'));
+        }
+        draw_depth(&mut buf);
+        let greys: Vec<[u8; 3]> = (0..3).map(|x| buf.get_pixel(x, 0).color).collect();
+        assert_eq!(greys, [[255; 3], [128; 3], [0; 3]]);
+        // Empty cells stay empty
+        assert_eq!(buf.get_pixel(3, 0).tex, ' ');
+        assert!(buf.get_pixel(3, 0).bg.is_none());
+    }
+
+    #[test]
+    fn views_cycle_back_to_none() {
+        let mut view = DebugView::next(None);
+        let mut seen = Vec::new();
+        while let Some(v) = view {
+            seen.push(v);
+            view = DebugView::next(view);
+        }
+        assert_eq!(seen, [DebugView::Normals, DebugView::Depth, DebugView::Overdraw, DebugView::ObjectId]);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Distance from the camera in grey, white up close and black from max onwards
+#[derive(Debug, Clone, Copy)]
+pub struct DepthShader {
+    pub max: f32,
+}
+
+impl DepthShader {
+    pub fn new(max: f32) -> Self {
+        Self { max }
+    }
+}
+
+impl Shader for DepthShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        let grey = ((1.0 - input.depth / self.max).clamp(0.0, 1.0) * 255.0).round() as u8;
+        input.base.with_color([grey; 3])
+    }
+    fn receives_shadows(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,19 @@
+use crate::math::vector::Vector;
+
+// Light arriving from the same direction everywhere, like the sun
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    // The way the light travels, so (0, -1, 0) shines straight down
+    pub direction: Vector,
+    pub casts_shadows: bool,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector, casts_shadows: bool) -> Self {
+        Self { direction: direction.normalized(), casts_shadows }
+    }
+    // Cosine of the angle between a normal and the way back to the light
+    pub fn facing(&self, normal: Vector) -> f32 {
+        normal.normalized().dot(-self.direction)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::stroke::Stroke;
+
+// Draws lines where depth jumps between neighbouring cells, which marks silhouettes and overlaps
+// whatever the shading looks like
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeDetect {
+    // Smallest jump in distance that counts, as a fraction of the nearer side's distance
+    pub threshold: f32,
+    pub color: [u8; 3],
+}
+
+impl EdgeDetect {
+    pub fn new(threshold: f32, color: [u8; 3]) -> Self {
+        Self { threshold, color }
+    }
+}
+
+// Depth is z / w, and 1 - z / w falls off as 1 / distance, so this grows with distance. Empty cells are
+// infinitely far.
+fn distance(depth: f32) -> f32 {
+    if depth.is_finite() { 1.0 / (1.0 - depth).max(1e-6) } else { f32::INFINITY }
+}
+
+impl PostProcess for EdgeDetect {
+    fn apply(&self, buf: &mut ScreenBuffer) {
+        let (width, height) = (buf.width as i32, buf.height as i32);
+        let mut distances = Vec::with_capacity((width * height) as usize);
+        for y in 0..buf.height {
+            for x in 0..buf.width {
+                distances.push(distance(buf.depth_at(x, y)));
+            }
+        }
+        let at = |x: i32, y: i32| distances[(y.clamp(0, height - 1) * width + x.clamp(0, width - 1)) as usize];
+        // Only the nearer side of a jump is marked, which keeps the lines one cell wide
+        let jump = |here: f32, there: f32| there > here && (there - here) > here * self.threshold;
+
+        for y in 0..height {
+            for x in 0..width {
+                let here = at(x, y);
+                if !here.is_finite() {
+                    continue;
+                }
+                let horizontal = jump(here, at(x - 1, y)) || jump(here, at(x + 1, y));
+                let vertical = jump(here, at(x, y - 1)) || jump(here, at(x, y + 1));
+                let tex = match (horizontal, vertical) {
+                    (true, true) => '+',
+                    (true, false) => '|',
+                    (false, true) => '-',
+                    (false, false) => continue,
+                };
+                let (x, y) = (x as u16, y as u16);
+                let bg = buf.get_pixel(x, y).bg;
+                buf.set_pixel(x, y, Stroke { bg, ..Stroke::new(self.color, tex) });
+            }
+        }
+    }
+}
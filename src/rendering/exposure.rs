@@ -0,0 +1,26 @@
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::screen_buffer::ScreenBuffer;
+
+// Scales brightness by 2^exposure, then applies a gamma curve
+#[derive(Debug, Clone, Copy)]
+pub struct Exposure {
+    pub exposure: f32,
+    pub gamma: f32,
+}
+
+impl Exposure {
+    pub fn new(exposure: f32, gamma: f32) -> Self {
+        Self { exposure, gamma }
+    }
+}
+
+impl PostProcess for Exposure {
+    fn apply(&self, buf: &mut ScreenBuffer) {
+        let scale = self.exposure.exp2();
+        let power = 1.0 / self.gamma.max(0.01);
+        buf.map_colors(|color| color.map(|c| {
+            let c = (c as f32 / 255.0 * scale).clamp(0.0, 1.0);
+            (c.powf(power) * 255.0).round() as u8
+        }));
+    }
+}
@@ -0,0 +1,8 @@
+// How a shaded face fills its cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillMode {
+    // The face's glyph in the shaded colour
+    Glyph,
+    // A space on the shaded colour, which leaves no gaps between cells whatever the font
+    Block,
+}
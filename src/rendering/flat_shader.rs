@@ -0,0 +1,13 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// The face's colour as is, ignoring the lights
+#[derive(Debug, Clone, Copy)]
+pub struct FlatShader;
+
+impl Shader for FlatShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        input.base
+    }
+}
@@ -0,0 +1,98 @@
+use crate::rendering::stroke::Stroke;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FogMode {
+    // Clear up to start, fully fogged from end
+    Linear { start: f32, end: f32 },
+    Exp { density: f32 },
+    Exp2 { density: f32 },
+}
+
+// Fades surfaces toward a colour with distance from the camera
+#[derive(Debug, Clone, Copy)]
+pub struct Fog {
+    pub mode: FogMode,
+    // None fades toward the background, worked out each frame by resolved
+    pub color: Option<[u8; 3]>,
+    // Also swaps in sparser glyphs with distance, for when colour alone doesn't show it
+    pub thin_glyphs: bool,
+}
+
+// Densest first
+const THIN_GLYPHS: [char; 9] = ['@', '%', '#', '*', '+', '=', '-', ':', '.'];
+
+impl Fog {
+    pub fn new(mode: FogMode, color: Option<[u8; 3]>, thin_glyphs: bool) -> Self {
+        Self { mode, color, thin_glyphs }
+    }
+    // This fog with a colour, taking the background's if it has none of its own
+    pub fn resolved(&self, background: [u8; 3]) -> Self {
+        Self { color: Some(self.color.unwrap_or(background)), ..*self }
+    }
+    // How much of the surface shows through at a view depth: 1 up close, 0 when fully fogged
+    pub fn visibility(&self, depth: f32) -> f32 {
+        let depth = depth.max(0.0);
+        let visibility = match self.mode {
+            FogMode::Linear { start, end } if end > start => (end - depth) / (end - start),
+            FogMode::Linear { start, .. } => if depth < start { 1.0 } else { 0.0 },
+            FogMode::Exp { density } => (-density * depth).exp(),
+            FogMode::Exp2 { density } => (-(density * depth).powi(2)).exp(),
+        };
+        visibility.clamp(0.0, 1.0)
+    }
+    pub fn apply(&self, stroke: Stroke, depth: f32) -> Stroke {
+        let visibility = self.visibility(depth);
+        let mut color = stroke.color;
+        for (c, fog) in color.iter_mut().zip(self.color.unwrap_or([0; 3])) {
+            *c = (fog as f32 + (*c as f32 - fog as f32) * visibility).round() as u8;
+        }
+        let tex = if self.thin_glyphs && visibility < 1.0 {
+            let i = ((1.0 - visibility) * THIN_GLYPHS.len() as f32) as usize;
+            THIN_GLYPHS[i.min(THIN_GLYPHS.len() - 1)]
+        } else {
+            stroke.tex
+        };
+        Stroke { tex, ..stroke.with_color(color) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::vector::Vector;
+    use crate::rendering::background::Background;
+    use crate::rendering::stroke::Stroke;
+    use crate::rendering::texture_filter::TextureFilter;
+    use super::{Fog, FogMode};
+
+    const LINEAR: FogMode = FogMode::Linear { start: 10.0, end: 20.0 };
+
+    #[test]
+    fn visibility_falls_off_with_depth() {
+        let fog = Fog::new(LINEAR, None, false);
+        assert_eq!(fog.visibility(5.0), 1.0);
+        assert!((fog.visibility(15.0) - 0.5).abs() < 1e-6);
+        assert_eq!(fog.visibility(30.0), 0.0);
+        let exp = Fog::new(FogMode::Exp { density: 0.1 }, None, false);
+        assert!(exp.visibility(10.0) < exp.visibility(5.0));
+    }
+
+    #[test]
+    fn colourless_fog_fades_toward_the_background() {
+        let background = Background::Gradient { horizon: [200, 150, 100], zenith: [0, 0, 255] };
+        // Looking up still fogs toward the horizon
+        let horizon = background.horizon(Vector::new(0.0, 0.8, 0.6), TextureFilter::Nearest);
+        assert_eq!(horizon, [200, 150, 100]);
+
+        let fog = Fog::new(LINEAR, None, false).resolved(horizon);
+        assert_eq!(fog.apply(Stroke::new([0, 0, 0], '#'), 100.0).color, [200, 150, 100]);
+        assert_eq!(fog.apply(Stroke::new([0, 0, 0], '#'), 0.0).color, [0, 0, 0]);
+    }
+
+    #[test]
+    fn explicit_colour_wins_over_the_background() {
+        let fog = Fog::new(LINEAR, Some([10, 20, 30]), true).resolved([200, 200, 200]);
+        let fogged = fog.apply(Stroke::new([255, 255, 255], '@'), 100.0);
+        assert_eq!(fogged.color, [10, 20, 30]);
+        assert_eq!(fogged.tex, '.');
+    }
+}
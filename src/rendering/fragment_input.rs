@@ -0,0 +1,22 @@
+use crate::math::vector::Vector;
+use crate::rendering::directional_light::DirectionalLight;
+use crate::rendering::stroke::Stroke;
+
+// Everything a shader gets to work with for one cell, or one vertex with Gouraud shading
+#[derive(Debug, Clone, Copy)]
+pub struct FragmentInput<'a> {
+    // World space
+    pub position: Vector,
+    pub normal: Vector,
+    pub uv: [f32; 2],
+    // Distance in front of the camera along its view axis
+    pub depth: f32,
+    pub object_id: usize,
+    // Seconds since the scene started
+    pub time: f32,
+    // The face's own colour, glyph and style
+    pub base: Stroke,
+    pub lights: &'a [DirectionalLight],
+    // Camera position in world space
+    pub eye: Vector,
+}
@@ -0,0 +1,11 @@
+use crate::math::vector::Vector;
+use crate::rendering::shadow_map::ShadowMap;
+
+// What every triangle drawn in a frame shares, besides the settings
+pub struct FrameContext {
+    pub shadow_maps: Vec<ShadowMap>,
+    // Camera position in world space
+    pub eye: Vector,
+    // Seconds since the scene started
+    pub time: f32,
+}
@@ -0,0 +1,67 @@
+use crate::math::vector::Vector;
+use crate::rendering::axis_gizmo::AXIS_COLORS;
+
+// Lines across the ground plane at y = 0, kept centred under the camera so it never runs out
+#[derive(Debug, Clone, Copy)]
+pub struct Grid {
+    pub spacing: f32,
+    // Lines on each side of the centre
+    pub extent: u16,
+    pub color: [u8; 3],
+}
+
+impl Grid {
+    pub fn new(spacing: f32, extent: u16, color: [u8; 3]) -> Self {
+        Self { spacing, extent, color }
+    }
+    // World space segments and their colours. The lines along the world x and z axes take the axis colours.
+    pub fn lines(&self, center: Vector) -> Vec<([Vector; 2], [u8; 3])> {
+        let extent = self.extent as i32;
+        let (cx, cz) = ((center.x / self.spacing).round() as i32, (center.z / self.spacing).round() as i32);
+        let (x0, x1) = ((cx - extent) as f32 * self.spacing, (cx + extent) as f32 * self.spacing);
+        let (z0, z1) = ((cz - extent) as f32 * self.spacing, (cz + extent) as f32 * self.spacing);
+
+        let mut lines = Vec::with_capacity(4 * self.extent as usize + 2);
+        for i in -extent..=extent {
+            let x = (cx + i) as f32 * self.spacing;
+            let color = if cx + i == 0 { AXIS_COLORS[2] } else { self.color };
+            lines.push(([Vector::new(x, 0.0, z0), Vector::new(x, 0.0, z1)], color));
+
+            let z = (cz + i) as f32 * self.spacing;
+            let color = if cz + i == 0 { AXIS_COLORS[0] } else { self.color };
+            lines.push(([Vector::new(x0, 0.0, z), Vector::new(x1, 0.0, z)], color));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::vector::Vector;
+    use crate::rendering::axis_gizmo::AXIS_COLORS;
+    use super::Grid;
+
+    #[test]
+    fn follows_the_camera_in_whole_steps() {
+        let grid = Grid::new(2.0, 3, [50; 3]);
+        let lines = grid.lines(Vector::new(10.9, 7.0, -13.2));
+        assert_eq!(lines.len(), 14);
+        // Snapped to the nearest line, (10, -14), with three spacings either side
+        let xs: Vec<f32> = lines.iter().step_by(2).map(|(ends, _)| ends[0].x).collect();
+        assert_eq!(xs, [4.0, 6.0, 8.0, 10.0, 12.0, 14.0, 16.0]);
+        assert!(lines.iter().all(|(ends, _)| ends.iter().all(|v| v.y == 0.0)));
+        assert!((lines[1].0[0] - Vector::new(4.0, 0.0, -20.0)).len() < 1e-6);
+        // The world axes are out of reach from here
+        assert!(lines.iter().all(|(_, color)| *color == [50; 3]));
+    }
+
+    #[test]
+    fn lines_on_the_world_axes_take_their_colours() {
+        let lines = Grid::new(1.0, 2, [50; 3]).lines(Vector::zero());
+        let along_z = lines.iter().find(|(ends, _)| ends[0].x == 0.0 && ends[1].x == 0.0).unwrap();
+        let along_x = lines.iter().find(|(ends, _)| ends[0].z == 0.0 && ends[1].z == 0.0).unwrap();
+        assert_eq!(along_z.1, AXIS_COLORS[2]);
+        assert_eq!(along_x.1, AXIS_COLORS[0]);
+        assert_eq!(lines.iter().filter(|(_, color)| *color == [50; 3]).count(), 8);
+    }
+}
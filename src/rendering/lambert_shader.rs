@@ -0,0 +1,35 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Diffuse lighting, averaged over the lights so adding one doesn't wash the scene out. Without any
+// lights the colour is kept.
+#[derive(Debug, Clone, Copy)]
+pub struct LambertShader {
+    // Half-Lambert: remaps the cosine from -1..1 to 0..1, so faces turned away from the light fade out
+    // instead of going black
+    pub half: bool,
+}
+
+impl LambertShader {
+    pub fn new(half: bool) -> Self {
+        Self { half }
+    }
+    pub fn intensity(&self, input: &FragmentInput) -> f32 {
+        if input.lights.is_empty() {
+            return 1.0;
+        }
+        let total: f32 = input.lights.iter().map(|light| {
+            let cos = light.facing(input.normal);
+            if self.half { (cos + 1.0) / 2.0 } else { cos.max(0.0) }
+        }).sum();
+        total / input.lights.len() as f32
+    }
+}
+
+impl Shader for LambertShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        let m = self.intensity(input);
+        input.base.with_color(input.base.color.map(|c| (c as f32 * m).round() as u8))
+    }
+}
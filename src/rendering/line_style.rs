@@ -0,0 +1,7 @@
+#[derive(Debug, Clone, Copy)]
+pub enum LineStyle {
+    // One of -|/\ per cell depending on the slope
+    Slope,
+    // 2x4 Braille dots per cell, for finer lines
+    Braille,
+}
@@ -0,0 +1,17 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// World space normal as a colour, x to red, y to green and z to blue, each from -1..1 to 0..255
+#[derive(Debug, Clone, Copy)]
+pub struct NormalShader;
+
+impl Shader for NormalShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        let n = input.normal.normalized();
+        input.base.with_color([n.x, n.y, n.z].map(|c| ((c + 1.0) / 2.0 * 255.0).round().clamp(0.0, 255.0) as u8))
+    }
+    fn receives_shadows(&self) -> bool {
+        false
+    }
+}
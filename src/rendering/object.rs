@@ -1,18 +1,34 @@
+use std::rc::Rc;
 use std::time::Duration;
 use crate::debug::debug_logger::log;
-use crate::interface::input::{ActiveCommand, CommandType, InterpolationMode};
+use crate::interface::input::{ActiveCommand, CommandType, Frame, InterpolationMode};
+use crate::math::aabb::Aabb;
 use crate::math::euler_rotation::EulerRotation;
+use crate::math::matrix::Matrix4;
 use crate::math::mesh::Mesh;
+use crate::math::obb::Obb;
 use crate::math::quaternion::Quaternion;
+use crate::math::transform::Transform;
 use crate::math::triangle::Triangle;
 use crate::math::vector::Vector;
+use crate::rendering::shader::Shader;
+use crate::rendering::text_style::TextStyle;
+use crate::rendering::texture::Texture;
 
 #[derive(Clone, Debug)]
 pub struct Object {
     pub base_mesh: Mesh,
     pub active_commands: Vec<ActiveCommand>,
-    rotation: Quaternion,
-    position: Vector,
+    pub parent: Option<usize>,
+    pub transform: Transform,
+    pub texture: Option<Rc<Texture>>,
+    // 1 is solid, 0 invisible
+    pub opacity: f32,
+    pub style: TextStyle,
+    // The default lighting where None
+    pub shader: Option<Rc<dyn Shader>>,
+    // Local transform including in-flight commands, as of the last apply_commands
+    current: Option<Transform>,
 }
 
 impl Object {
@@ -20,10 +36,39 @@ impl Object {
         Object {
             base_mesh: m,
             active_commands: Vec::new(),
-            rotation: Quaternion::identity(),
-            position: Vector::new(0.0, 0.0, 0.0),
+            parent: None,
+            transform: Transform::identity(),
+            texture: None,
+            opacity: 1.0,
+            style: TextStyle::default(),
+            shader: None,
+            current: None,
         }
     }
+    pub fn set_position(&mut self, position: Vector) {
+        self.transform.translation = position;
+    }
+    pub fn set_rotation(&mut self, rotation: Quaternion) {
+        self.transform.rotation = rotation;
+    }
+    pub fn set_scale(&mut self, scale: Vector) {
+        self.transform.scale = scale;
+    }
+    // Moves the pivot without moving the object
+    pub fn set_pivot(&mut self, pivot: Vector) {
+        self.transform = self.transform.with_pivot(pivot);
+    }
+    // The settled transform until commands have been applied once
+    pub fn current_transform(&self) -> Transform {
+        self.current.unwrap_or(self.transform)
+    }
+    // Bounds in world space, given the object's world matrix
+    pub fn oriented_bounds(&self, model: &Matrix4) -> Obb {
+        Obb::from_aabb(&self.base_mesh.bounds(), model)
+    }
+    pub fn world_bounds(&self, model: &Matrix4) -> Aabb {
+        self.oriented_bounds(model).aabb()
+    }
     pub fn add_command(&mut self, command: ActiveCommand) {
         self.active_commands.push(command);
     }
@@ -33,57 +78,236 @@ impl Object {
             cmd.time_passed += time;
         }
     }
-    pub fn apply_commands(&mut self) -> Mesh {
-        let mut total_translation = self.position;
-        let mut total_rotation = self.rotation;
-        let mut over = false;
+    // Advances the active commands and returns the local transform for this frame. The parent's world
+    // transform is needed to turn world-frame commands into the object's local space.
+    pub fn apply_commands(&mut self, parent: &Transform) -> Transform {
+        let parent_inverse = parent.rotation.normalized().conjugate();
+        // Finished commands are folded into the transform first, so the frame they finish on already shows them
+        let (finished, running): (Vec<_>, Vec<_>) = self.active_commands.drain(..).partition(is_finished);
+        for cmd in &finished {
+            self.transform = apply_command(self.transform, cmd, parent, parent_inverse);
+        }
+        let mut total = self.transform;
+        for cmd in &running {
+            total = apply_command(total, cmd, parent, parent_inverse);
+        }
+        self.active_commands = running;
+
+        self.current = Some(total);
+        total
+    }
+}
+
+fn is_finished(cmd: &ActiveCommand) -> bool {
+    match cmd.interpolation {
+        InterpolationMode::Instant => true,
+        InterpolationMode::Linear { duration } => cmd.time_passed >= duration,
+        InterpolationMode::Continuous | InterpolationMode::Oscillation { .. } => false,
+    }
+}
+
+// Every command is evaluated from the time since it started rather than accumulated per frame
+fn apply_command(mut target: Transform, cmd: &ActiveCommand, parent: &Transform, parent_inverse: Quaternion) -> Transform {
+    let mut translation = Vector::zero();
+    let mut rotation = Quaternion::identity();
+    let mut scale = Vector::new(1.0, 1.0, 1.0);
+    let mut frame = Frame::Local;
+
+    match cmd.command {
+        CommandType::Move { delta, frame: Frame::World } => {
+            translation += (parent_inverse * delta).div_components(parent.scale);
+            frame = Frame::World;
+        }
+        CommandType::Move { delta, frame: Frame::Local } => translation += target.rotation * delta,
+        CommandType::Rotate { delta, frame: f } => {
+            rotation = rotation * delta;
+            frame = f;
+        }
+        CommandType::Scale { factor } => scale = factor,
+    }
+
+    let coef: f64 = match cmd.interpolation {
+        InterpolationMode::Instant => 1.0,
+        InterpolationMode::Linear { duration } => (cmd.time_passed.as_secs_f64() / duration.as_secs_f64()).min(1.0),
+        InterpolationMode::Continuous => cmd.time_passed.as_secs_f64(),
+        InterpolationMode::Oscillation { period } => todo!()
+    };
 
-        for cmd in &self.active_commands {
-            let mut translation = Vector::zero();
-            let mut rotation = Quaternion::identity();
+    translation *= coef as f32;
+    rotation = match cmd.interpolation {
+        InterpolationMode::Continuous => rotation.powf(coef),
+        _ => Quaternion::identity().slerp(rotation, coef as f32),
+    };
+    // Scaling compounds, so interpolate the exponent: half way to 4x is 2x
+    let coef = coef as f32;
+    let scale = Vector::new(scale_power(scale.x, coef), scale_power(scale.y, coef), scale_power(scale.z, coef));
 
-            match cmd.command {
-                CommandType::Move { delta } => translation += delta,
-                CommandType::Rotate { delta } => rotation = rotation * delta,
+    target.translation += translation;
+    // Renormalized after every product so completed commands can't build up drift
+    target.rotation = match frame {
+        // Conjugating by the parent's rotation turns a world axis into the parent's space
+        Frame::World => parent_inverse * rotation * parent.rotation * target.rotation,
+        Frame::Local => target.rotation * rotation,
+    }.normalized();
+    target.scale = target.scale.mul_components(scale);
+    target
+}
+
+// A negative factor mirrors the object, and a fractional power of it would be NaN. The size grows
+// by the magnitude and the mirror flips once per whole step, like repeatedly applying the factor.
+fn scale_power(factor: f32, coef: f32) -> f32 {
+    let magnitude = factor.abs().powf(coef);
+    if factor < 0.0 && coef.floor() % 2.0 == 1.0 { -magnitude } else { magnitude }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::interface::input::{ActiveCommand, CommandType, Frame, InterpolationMode};
+    use crate::math::matrix::Matrix4;
+    use crate::math::mesh::Mesh;
+    use crate::math::quaternion::Quaternion;
+    use crate::math::transform::Transform;
+    use crate::math::vector::Vector;
+    use super::Object;
+
+    fn command(command: CommandType, interpolation: InterpolationMode) -> ActiveCommand {
+        ActiveCommand { command, interpolation, time_passed: Duration::ZERO }
+    }
+
+    // Between every pair of vertexes, after transforming them
+    fn distances(mesh: &Mesh, m: &Matrix4) -> Vec<f32> {
+        let verts: Vec<Vector> = mesh.vertexes.iter().map(|v| Vector::from(m.transform_vertex(*v))).collect();
+        let mut distances = Vec::new();
+        for (i, a) in verts.iter().enumerate() {
+            for b in &verts[i + 1..] {
+                distances.push((*a - *b).len());
             }
+        }
+        distances
+    }
 
-            let coef: f32 = match cmd.interpolation {
-                InterpolationMode::Instant => {
-                    over = true;
-                    1.0
-                },
-                InterpolationMode::Linear { duration } => {
-                    over = cmd.time_passed > duration;
-                    cmd.time_passed.as_secs_f32() / duration.as_secs_f32()
-                },
-                InterpolationMode::Continuous => cmd.time_passed.as_secs_f32(),
-                InterpolationMode::Oscillation { period } => todo!()
-            };
-
-            translation *= coef;
-            rotation = Quaternion::identity().slerp(rotation, coef);
-            
-            if over {
-                self.position += translation;
-                self.rotation = self.rotation * rotation;
+    #[test]
+    fn continuous_rotation_stays_rigid_for_hours() {
+        let mesh = Mesh::cuboid(2.0, 4.0, 6.0);
+        let mut obj = Object::new(mesh.clone());
+        let spin = Quaternion::from_axis_angle(Vector::new(1.0, 2.0, 3.0).normalized(), 1.3);
+        let turn = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), 0.7);
+        obj.add_command(command(CommandType::Rotate { delta: spin, frame: Frame::Local }, InterpolationMode::Continuous));
+        obj.add_command(command(CommandType::Rotate { delta: turn, frame: Frame::World }, InterpolationMode::Linear { duration: Duration::from_secs(5) }));
+        obj.add_command(command(CommandType::Move { delta: Vector::new(1.0, 0.0, 0.0), frame: Frame::World }, InterpolationMode::Linear { duration: Duration::from_secs(3) }));
+
+        let expected = distances(&mesh, &Matrix4::identity());
+        let step = Duration::from_millis(100);
+        // Six hours at ten frames a second
+        for frame in 0..6 * 3600 * 10 {
+            obj.pass_time(step);
+            let transform = obj.apply_commands(&Transform::identity());
+            if frame % 997 != 0 {
+                continue;
             }
-            else {
-                total_translation += translation;
-                total_rotation = total_rotation * rotation;
+            assert!((transform.rotation.len() - 1.0).abs() < 1e-4, "rotation length {} at frame {}", transform.rotation.len(), frame);
+            let actual = distances(&mesh, &Matrix4::from_transform(&transform));
+            for (a, e) in actual.iter().zip(&expected) {
+                assert!((a - e).abs() < 1e-3 * e.max(1.0), "distance {} became {} at frame {}", e, a, frame);
             }
         }
+        // Only the spin is left, and the finished commands landed in full
+        assert_eq!(obj.active_commands.len(), 1);
+        assert!((obj.transform.translation.x - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn linear_move_lands_on_target_when_time_hits_duration() {
+        let mut obj = Object::new(Mesh::cuboid(1.0, 1.0, 1.0));
+        obj.add_command(command(CommandType::Move { delta: Vector::new(1.0, 0.0, 0.0), frame: Frame::World }, InterpolationMode::Linear { duration: Duration::from_secs(1) }));
+        for _ in 0..10 {
+            obj.pass_time(Duration::from_millis(100));
+            obj.apply_commands(&Transform::identity());
+        }
+        // The frame the command finishes on already shows all of it
+        assert!((obj.current_transform().translation.x - 1.0).abs() < 1e-5);
+        for _ in 0..5 {
+            obj.pass_time(Duration::from_millis(100));
+            obj.apply_commands(&Transform::identity());
+        }
+        assert!((obj.current_transform().translation.x - 1.0).abs() < 1e-5);
+        assert!(obj.active_commands.is_empty());
+    }
 
-        self.active_commands.retain(|cmd| {
-           match cmd.interpolation {
-               InterpolationMode::Instant => false,
-               InterpolationMode::Continuous => true,
-               InterpolationMode::Linear { duration } => {
-                   duration.as_secs_f32() > cmd.time_passed.as_secs_f32()
-               },
-               InterpolationMode::Oscillation { .. } => true,
-           }
-        });
+    #[test]
+    fn negative_scale_stays_finite_while_interpolating() {
+        let mut obj = Object::new(Mesh::cuboid(1.0, 1.0, 1.0));
+        let mirror = CommandType::Scale { factor: Vector::new(-4.0, 1.0, 1.0) };
+        obj.add_command(command(mirror, InterpolationMode::Linear { duration: Duration::from_secs(1) }));
+        obj.pass_time(Duration::from_millis(500));
+        let half = obj.apply_commands(&Transform::identity());
+        assert!((half.scale.x - 2.0).abs() < 1e-5, "half way scale was {}", half.scale.x);
+        obj.pass_time(Duration::from_millis(500));
+        obj.apply_commands(&Transform::identity());
+        assert!((obj.transform.scale.x + 4.0).abs() < 1e-5);
 
-        self.base_mesh.clone().rotate(&total_rotation).translate(&total_translation)
+        // Continuous mirroring flips once a second
+        let mut obj = Object::new(Mesh::cuboid(1.0, 1.0, 1.0));
+        obj.add_command(command(CommandType::Scale { factor: Vector::new(-1.0, 1.0, 1.0) }, InterpolationMode::Continuous));
+        for (millis, expected) in [(500, 1.0), (1000, -1.0), (1500, -1.0), (2000, 1.0)] {
+            obj.active_commands[0].time_passed = Duration::from_millis(millis);
+            let transform = obj.apply_commands(&Transform::identity());
+            assert_eq!(transform.scale.x, expected, "at {}ms", millis);
+        }
+    }
+
+    // Where the object's own +z axis ends up
+    fn forward(transform: &Transform) -> Vector {
+        transform.rotation * Vector::new(0.0, 0.0, 1.0)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rotations_turn_about_local_or_world_axes() {
+        let quarter = std::f32::consts::FRAC_PI_2;
+        let about_x = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), quarter);
+        let turned = |frame: Frame, parent: &Transform| {
+            let mut obj = Object::new(Mesh::cuboid(1.0, 1.0, 1.0));
+            // Facing +x, so its own x axis points along world -z
+            obj.set_rotation(Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), quarter));
+            obj.add_command(command(CommandType::Rotate { delta: about_x, frame }, InterpolationMode::Instant));
+            obj.apply_commands(parent)
+        };
+
+        // Its own x axis is across its facing, so turning about it tips the object over
+        let local = turned(Frame::Local, &Transform::identity());
+        assert!((forward(&local) - Vector::new(0.0, -1.0, 0.0)).len() < 1e-5);
+        // Turning about the world's x axis, +x stays put and the object spins around it
+        let world = turned(Frame::World, &Transform::identity());
+        assert!((forward(&world) - Vector::new(1.0, 0.0, 0.0)).len() < 1e-5);
+        assert!((world.rotation * Vector::new(0.0, 1.0, 0.0) - Vector::new(0.0, 0.0, 1.0)).len() < 1e-5);
+
+        // Under a parent turned about z, the world axis is carried into the parent's space
+        let parent = Transform { rotation: Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), quarter), ..Transform::identity() };
+        let child = turned(Frame::World, &parent);
+        let in_world = parent.compose(&child);
+        let expected = about_x * parent.rotation * Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), quarter);
+        for axis in [Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 0.0, 1.0)] {
+            assert!((in_world.rotation * axis - expected * axis).len() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn moves_follow_local_or_world_axes() {
+        let moved = |frame: Frame, parent: &Transform| {
+            let mut obj = Object::new(Mesh::cuboid(1.0, 1.0, 1.0));
+            obj.set_rotation(Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2));
+            obj.add_command(command(CommandType::Move { delta: Vector::new(0.0, 0.0, 2.0), frame }, InterpolationMode::Instant));
+            parent.compose(&obj.apply_commands(parent)).translation
+        };
+        assert!((moved(Frame::Local, &Transform::identity()) - Vector::new(2.0, 0.0, 0.0)).len() < 1e-5);
+        assert!((moved(Frame::World, &Transform::identity()) - Vector::new(0.0, 0.0, 2.0)).len() < 1e-5);
+        // World moves cover the same world distance under a scaled, turned parent
+        let parent = Transform {
+            rotation: Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), 0.7),
+            scale: Vector::new(4.0, 4.0, 4.0),
+            ..Transform::identity()
+        };
+        assert!((moved(Frame::World, &parent) - Vector::new(0.0, 0.0, 2.0)).len() < 1e-5);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// A flat colour per object, with hues spread by the golden angle so neighbouring ids stand apart
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectIdShader;
+
+impl ObjectIdShader {
+    pub fn color(id: usize) -> [u8; 3] {
+        let hue = (id as f32 * 137.508).rem_euclid(360.0) / 60.0;
+        let x = 1.0 - (hue.rem_euclid(2.0) - 1.0).abs();
+        let [r, g, b] = match hue as u32 {
+            0 => [1.0, x, 0.0],
+            1 => [x, 1.0, 0.0],
+            2 => [0.0, 1.0, x],
+            3 => [0.0, x, 1.0],
+            4 => [x, 0.0, 1.0],
+            _ => [1.0, 0.0, x],
+        };
+        [r, g, b].map(|c: f32| (55.0 + c * 200.0).round() as u8)
+    }
+}
+
+impl Shader for ObjectIdShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        input.base.with_color(Self::color(input.object_id))
+    }
+    fn receives_shadows(&self) -> bool {
+        false
+    }
+}
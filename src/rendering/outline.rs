@@ -0,0 +1,73 @@
+use crate::math::mesh::{Edge, Mesh};
+
+// Edges drawn over the shaded fill so shapes stay readable at low resolution
+#[derive(Debug, Clone, Copy)]
+pub struct Outline {
+    pub color: [u8; 3],
+    // Edges whose faces meet at more than this (radians) are outlined even when both faces are visible
+    pub crease_angle: f32,
+}
+
+impl Outline {
+    pub fn new(color: [u8; 3], crease_angle: f32) -> Self {
+        Self { color, crease_angle }
+    }
+    // Silhouette edges between a face turned towards the camera and one turned away, crease edges
+    // between two visible faces, and the open border of visible faces. clip holds the mesh's
+    // vertexes in clip space.
+    pub fn edges<'a>(&self, mesh: &Mesh, edges: &'a [Edge], clip: &[[f32; 4]]) -> Vec<&'a Edge> {
+        let front: Vec<bool> = mesh.faces.iter().map(|f| facing(f.verts.map(|v| clip[v])) < 0.0).collect();
+        let normals: Vec<_> = mesh.faces.iter().map(|f| mesh.face_normal(f)).collect();
+        let min_cos = self.crease_angle.cos();
+
+        edges.iter().filter(|edge| {
+            let visible = edge.faces.iter().filter(|&&f| front[f]).count();
+            match (edge.faces.len(), visible) {
+                (_, 0) => false,
+                (1, _) => true,
+                (n, v) if v < n => true,
+                _ => {
+                    let first = normals[edge.faces[0]];
+                    edge.faces[1..].iter().any(|&f| first.dot(normals[f]) < min_cos)
+                }
+            }
+        }).collect()
+    }
+}
+
+// Twice the signed area of the projected triangle, scaled by the product of the w's. It's computed
+// without dividing, so it holds for any projection and for vertexes behind the camera. Faces the
+// rasterizer draws come out negative, since screen y points down.
+fn facing([a, b, c]: [[f32; 4]; 3]) -> f32 {
+    a[0] * (b[1] * c[3] - b[3] * c[1])
+        - a[1] * (b[0] * c[3] - b[3] * c[0])
+        + a[3] * (b[0] * c[1] - b[1] * c[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::matrix::Matrix4;
+    use crate::math::mesh::Mesh;
+    use crate::math::vector::Vector;
+    use super::Outline;
+
+    // How many edges of a cube seen from eye get outlined
+    fn outlined(eye: Vector, crease_degrees: f32) -> usize {
+        let cube = Mesh::cuboid(2.0, 2.0, 2.0);
+        let view = Matrix4::look_at(eye, Vector::zero(), Vector::new(0.0, 1.0, 0.0));
+        let m = Matrix4::perspective(1.0, 1.0, 0.1, 100.0) * view;
+        let clip: Vec<[f32; 4]> = cube.vertexes.iter().map(|v| m.transform_point([v.x, v.y, v.z, 1.0])).collect();
+        Outline::new([0; 3], crease_degrees.to_radians()).edges(&cube, cube.edges(), &clip).len()
+    }
+
+    #[test]
+    fn outlines_silhouettes_and_creases() {
+        // Head on only the front is visible, so its border is the whole silhouette
+        assert_eq!(outlined(Vector::new(0.0, 0.0, -6.0), 30.0), 4);
+        // From a corner three sides show: a hexagon around them, plus the three edges between them
+        // when they meet at more than the crease angle. The diagonals across each side never count.
+        let corner = Vector::new(4.0, 5.0, -6.0);
+        assert_eq!(outlined(corner, 100.0), 6);
+        assert_eq!(outlined(corner, 30.0), 9);
+    }
+}
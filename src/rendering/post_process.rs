@@ -0,0 +1,53 @@
+use std::fmt::Debug;
+use crate::rendering::screen_buffer::ScreenBuffer;
+
+// A pass over the finished frame, run in the order they were added
+pub trait PostProcess: Debug {
+    fn apply(&self, buf: &mut ScreenBuffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::edge_detect::EdgeDetect;
+    use crate::rendering::exposure::Exposure;
+    use crate::rendering::posterize::Posterize;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use crate::rendering::stroke::Stroke;
+    use super::PostProcess;
+
+    // The colour one cell ends up with after a pass
+    fn pass(post: &dyn PostProcess, color: [u8; 3]) -> [u8; 3] {
+        let mut buf = ScreenBuffer::new(1, 1);
+        buf.set_pixel(0, 0, Stroke::new(color, '#'));
+        post.apply(&mut buf);
+        buf.get_pixel(0, 0).color
+    }
+
+    #[test]
+    fn posterize_snaps_channels_or_brightness() {
+        assert_eq!(pass(&Posterize::new(3, false), [0, 100, 200]), [0, 127, 255]);
+        // Cel mode keeps the hue
+        assert_eq!(pass(&Posterize::new(2, true), [200, 100, 0]), [255, 128, 0]);
+        assert_eq!(pass(&Posterize::new(2, true), [100, 50, 0]), [0, 0, 0]);
+    }
+
+    #[test]
+    fn exposure_scales_then_applies_gamma() {
+        assert_eq!(pass(&Exposure::new(1.0, 1.0), [100, 200, 0]), [200, 255, 0]);
+        assert_eq!(pass(&Exposure::new(0.0, 2.0), [64, 255, 0]), [128, 255, 0]);
+    }
+
+    #[test]
+    fn edge_detect_marks_the_near_side_of_depth_jumps() {
+        let mut buf = ScreenBuffer::new(3, 1);
+        for x in 0..2 {
+            buf.test_and_set_depth(x, 0, 0.5);
+            buf.set_pixel(x, 0, Stroke::filled([50; 3]));
+        }
+        EdgeDetect::new(0.1, [255, 0, 0]).apply(&mut buf);
+        assert_eq!(buf.get_pixel(0, 0).tex, ' ');
+        let edge = buf.get_pixel(1, 0);
+        assert_eq!((edge.tex, edge.color, edge.bg), ('|', [255, 0, 0], Some([50; 3])));
+        assert_eq!(buf.get_pixel(2, 0).tex, ' ');
+    }
+}
@@ -0,0 +1,37 @@
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::screen_buffer::ScreenBuffer;
+
+// Snaps colours to a few levels per channel, or with cel set, snaps brightness and keeps the hue
+#[derive(Debug, Clone, Copy)]
+pub struct Posterize {
+    pub levels: u8,
+    pub cel: bool,
+}
+
+impl Posterize {
+    pub fn new(levels: u8, cel: bool) -> Self {
+        Self { levels: levels.max(2), cel }
+    }
+    fn quantize(&self, value: f32) -> f32 {
+        let steps = (self.levels - 1) as f32;
+        (value / 255.0 * steps).round() / steps * 255.0
+    }
+}
+
+impl PostProcess for Posterize {
+    fn apply(&self, buf: &mut ScreenBuffer) {
+        buf.map_colors(|color| {
+            let color = color.map(|c| c as f32);
+            if self.cel {
+                let brightness = color.iter().cloned().fold(0.0, f32::max);
+                if brightness == 0.0 {
+                    return [0; 3];
+                }
+                let scale = self.quantize(brightness) / brightness;
+                color.map(|c| (c * scale).round().clamp(0.0, 255.0) as u8)
+            } else {
+                color.map(|c| self.quantize(c) as u8)
+            }
+        });
+    }
+}
@@ -0,0 +1,28 @@
+use std::f32::consts::TAU;
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::lambert_shader::LambertShader;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Default lighting that fades toward a colour and back over each period, to draw the eye to an object
+#[derive(Debug, Clone, Copy)]
+pub struct PulseShader {
+    pub color: [u8; 3],
+    // Seconds
+    pub period: f32,
+}
+
+impl PulseShader {
+    pub fn new(color: [u8; 3], period: f32) -> Self {
+        Self { color, period: period.max(0.01) }
+    }
+}
+
+impl Shader for PulseShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        let lit = LambertShader::new(true).shade(input);
+        let t = (1.0 - (input.time / self.period * TAU).cos()) / 2.0;
+        let mixed = Stroke::new(self.color, lit.tex).over(lit.color, t * 0.6);
+        lit.with_color(mixed.color)
+    }
+}
@@ -1,50 +1,457 @@
 use crate::math::vertex::Vertex;
-use crate::math::triangle::Triangle;
+use crate::math::vector::Vector;
 use crate::rendering::camera::Camera;
 use crate::math::geometry::signed_area;
-use crate::math::projection_type::ProjectionType;
+use crate::rendering::clipping::ClipVertex;
+use crate::rendering::fill_mode::FillMode;
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::frame_context::FrameContext;
 use crate::rendering::point::Point;
+use crate::rendering::line_style::LineStyle;
+use crate::rendering::render_mode::RenderMode;
+use crate::rendering::render_settings::RenderSettings;
+use crate::rendering::render_target::RenderTarget;
 use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::shader::Shader;
+use crate::rendering::shading_mode::ShadingMode;
+use crate::rendering::shadow_map::ShadowMap;
+use crate::rendering::shadows::Shadows;
+use crate::rendering::stroke::Stroke;
+use crate::rendering::texture::Texture;
+use crate::rendering::varyings::Varyings;
 
-pub fn draw_triangle(buf: &mut ScreenBuffer, tri: &Triangle, cam: &Camera, prj_type: &ProjectionType) {
-    let vertexes = tri.verts.map(|v| cam.project(v, prj_type));
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenVertex {
+    pub point: Point,
+    // z / w, from 0 at the near plane to 1 at the far plane
+    pub depth: f32,
+    // Attributes divided by w interpolate linearly across the screen, so this undoes the division
+    pub inv_w: f32,
+    pub varyings: Varyings,
+}
+
+impl ScreenVertex {
+    // Perspective divide and viewport transform
+    pub fn from_clip(v: &ClipVertex, cam: &Camera) -> ScreenVertex {
+        let [x, y, z, w] = v.pos;
+        ScreenVertex {
+            point: cam.viewport(x / w, y / w),
+            depth: z / w,
+            inv_w: 1.0 / w,
+            varyings: v.varyings,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenTriangle<'a> {
+    pub verts: [ScreenVertex; 3],
+    pub face_normal: Vector,
+    pub stroke: Stroke,
+    // Only set when the face has texture coordinates
+    pub texture: Option<&'a Texture>,
+    pub opacity: f32,
+    pub shader: &'a dyn Shader,
+    pub object_id: usize,
+}
 
-    let min_x = (*vertexes.map(|v| v.x.round() as i32).iter().min().unwrap()).max(0);
-    let min_y = (*vertexes.map(|v| v.y.round() as i32).iter().min().unwrap()).max(0);
-    let max_x = (*vertexes.map(|v| v.x.round() as i32).iter().max().unwrap()).min(buf.width as i32);
-    let max_y = (*vertexes.map(|v| v.y.round() as i32).iter().max().unwrap()).min(buf.height as i32);
+impl ScreenTriangle<'_> {
+    pub fn avg_depth(&self) -> f32 {
+        self.verts.iter().map(|v| v.depth).sum::<f32>() / 3.0
+    }
+    // The same triangle on a grid with factor times as many cells each way
+    pub fn scaled(&self, factor: f32) -> Self {
+        let mut tri = *self;
+        for v in tri.verts.iter_mut() {
+            v.point = Point::new(v.point.x * factor, v.point.y * factor);
+        }
+        tri
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenLine {
+    pub verts: [ScreenVertex; 2],
+    pub stroke: Stroke,
+}
 
-    let e = [
-        (vertexes[0], vertexes[1]),
-        (vertexes[1], vertexes[2]),
-        (vertexes[2], vertexes[0]),
-    ];
+// How far behind a surface a line may be and still show, as a fraction of its distance. Edges lie
+// exactly on their faces, which are only sampled at cell centres.
+const LINE_DEPTH_BIAS: f32 = 0.05;
 
+// Braille dot bits by column and row within a cell
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+const BRAILLE_BASE: u32 = 0x2800;
+
+// Calls plot with the barycentric weights of every cell whose centre is inside the triangle. Edge
+// functions are evaluated once per row and stepped across it, and shared edges follow the top-left
+// rule so no cell is drawn twice.
+pub fn rasterize<F: FnMut(u16, u16, [f32; 3])>(width: u16, height: u16, vertexes: [Point; 3], mut plot: F) {
+    let area = signed_area(vertexes[0], vertexes[1], vertexes[2]);
+    if area <= 0.0 {
+        return;
+    }
+
+    let min_x = (*vertexes.map(|v| v.x.floor() as i32).iter().min().unwrap()).max(0);
+    let min_y = (*vertexes.map(|v| v.y.floor() as i32).iter().min().unwrap()).max(0);
+    let max_x = (*vertexes.map(|v| v.x.ceil() as i32).iter().max().unwrap()).min(width as i32);
+    let max_y = (*vertexes.map(|v| v.y.ceil() as i32).iter().max().unwrap()).min(height as i32);
+
+    // Edge i runs from vertex i to i + 1, and is opposite vertex (i + 2) % 3
+    let edges = [0, 1, 2].map(|i| (vertexes[i], vertexes[(i + 1) % 3]));
+    let steps = edges.map(|(p0, p1)| p0.y - p1.y);
+    let top_left = edges.map(|(p0, p1)| is_top_left(p0, p1));
 
     for y in min_y..max_y {
+        let start = Point::new(min_x as f32 + 0.5, y as f32 + 0.5);
+        let mut e = edges.map(|(p0, p1)| signed_area(p0, p1, start));
         for x in min_x..max_x {
-            let p = Point::new(x as f32 + 0.5, y as f32 + 0.5);
-
-            let mut inside = true;
-            for (i, (p0, p1)) in e.iter().enumerate() {
-                let a = signed_area(*p0, *p1, p);
-                let top_left = is_top_left(*p0, *p1);
-                if a < 0.0 || (a == 0.0 && !top_left) {
-                    inside = false;
-                    break;
-                }
-            }
-            
+            let inside = (0..3).all(|i| e[i] > 0.0 || (e[i] == 0.0 && top_left[i]));
             if inside {
-                buf.set_pixel(x as u16, y as u16, tri.stroke.shaded(tri.normal()));
+                plot(x as u16, y as u16, [e[1] / area, e[2] / area, e[0] / area]);
+            }
+            for i in 0..3 {
+                e[i] += steps[i];
             }
         }
     }
 }
 
+// Screen space weights are only right for attributes divided by w. Weighting by 1 / w and
+// renormalizing gives the weights for the attributes themselves.
+fn perspective_weights(verts: &[ScreenVertex; 3], weights: [f32; 3]) -> [f32; 3] {
+    let scaled = [0, 1, 2].map(|i| weights[i] * verts[i].inv_w);
+    let sum: f32 = scaled.iter().sum();
+    scaled.map(|w| w / sum)
+}
+
+fn perspective_weights_line(verts: &[ScreenVertex; 2], t: f32) -> [f32; 2] {
+    let scaled = [(1.0 - t) * verts[0].inv_w, t * verts[1].inv_w];
+    let sum = scaled[0] + scaled[1];
+    scaled.map(|w| w / sum)
+}
+
+// The shading mode decides which normal the shader sees: the face's, or one interpolated from the
+// vertexes, and whether it runs once per vertex or once per cell
+pub fn draw_triangle<T: RenderTarget>(buf: &mut T, tri: &ScreenTriangle, settings: &RenderSettings, frame: &FrameContext) {
+    let input = |v: &Varyings, normal: Vector| FragmentInput {
+        position: v.position,
+        normal,
+        uv: v.uv,
+        depth: v.depth,
+        object_id: tri.object_id,
+        time: frame.time,
+        base: tri.stroke,
+        lights: &settings.lights,
+        eye: frame.eye,
+    };
+    let mut verts = tri.verts;
+    if let ShadingMode::Gouraud = settings.shading {
+        for v in verts.iter_mut() {
+            v.varyings.color = tri.shader.shade(&input(&v.varyings, v.varyings.normal)).color.map(|c| c as f32);
+        }
+    }
+
+    // Transparent faces are drawn after the opaque ones and don't hide anything
+    let opaque = tri.opacity >= 1.0;
+    rasterize(buf.width(), buf.height(), verts.map(|v| v.point), |x, y, weights| {
+        // z / w is affine in screen space, so depth uses the screen weights directly
+        let depth: f32 = verts.iter().zip(weights).map(|(v, w)| v.depth * w).sum();
+        let visible = if opaque { buf.test_and_set_depth(x, y, depth) } else { buf.test_depth(x, y, depth) };
+        if !visible || settings.render_mode == RenderMode::HiddenLine {
+            return;
+        }
+
+        let attrs = Varyings::blend(&verts.map(|v| v.varyings), perspective_weights(&verts, weights));
+        let mut stroke = match settings.shading {
+            ShadingMode::Flat => tri.shader.shade(&input(&attrs, tri.face_normal)),
+            ShadingMode::Gouraud => tri.stroke.with_color(attrs.color.map(|c| c.round().clamp(0.0, 255.0) as u8)),
+            ShadingMode::Phong => tri.shader.shade(&input(&attrs, attrs.normal)),
+        };
+        if let Some(shadows) = settings.shadows && !frame.shadow_maps.is_empty() && tri.shader.receives_shadows() {
+            let visibility = shadow_visibility(&frame.shadow_maps, settings, &shadows, attrs.position, tri.face_normal);
+            stroke = stroke.modulated([255.0 * visibility; 3]);
+        }
+        if let Some(texture) = tri.texture {
+            stroke = stroke.modulated(texture.sample(attrs.uv[0], attrs.uv[1], settings.texture_filter));
+        }
+        if let Some(fog) = settings.fog {
+            stroke = fog.apply(stroke, attrs.depth);
+        }
+        if settings.fill == FillMode::Block {
+            stroke = Stroke::filled(stroke.color).with_style(stroke.style);
+        }
+        if opaque {
+            buf.set_pixel(x, y, stroke);
+        }
+        else {
+            buf.blend_pixel(x, y, stroke, tri.opacity, depth);
+        }
+    });
+}
+
+// Lights are averaged when shading, so each shadow takes away its light's share
+fn shadow_visibility(shadow_maps: &[ShadowMap], settings: &RenderSettings, shadows: &Shadows, position: Vector, normal: Vector) -> f32 {
+    let shadowed: f32 = shadow_maps.iter().map(|map| 1.0 - map.lit(position, normal, shadows)).sum();
+    1.0 - shadows.strength * shadowed / settings.lights.len() as f32
+}
 
 fn is_top_left(a: Point, b: Point) -> bool {
     // Top edge: y1 == y2 and x1 < x2 (horizontal, left to right)
     // Left edge: y1 < y2 (vertical, top to bottom)
     (a.y == b.y && a.x < b.x) || (a.y < b.y)
-}
\ No newline at end of file
+}
+
+// Lines are drawn on whatever background the cell already has
+fn set_line_pixel(buf: &mut ScreenBuffer, x: u16, y: u16, stroke: Stroke) {
+    let bg = buf.get_pixel(x, y).bg;
+    buf.set_pixel(x, y, Stroke { bg, ..stroke });
+}
+
+// Lines don't write depth, so they never hide each other. They're depth tested against the faces
+// unless the faces aren't drawn.
+pub fn draw_line(buf: &mut ScreenBuffer, line: &ScreenLine, settings: &RenderSettings) {
+    let [a, b] = line.verts;
+    let depth_test = settings.render_mode.draws_faces();
+    let stroke_at = |t: f32, tex: char| {
+        let stroke = Stroke::new(line.stroke.color, tex);
+        match settings.fog {
+            Some(fog) => {
+                let weights = perspective_weights_line(&line.verts, t);
+                fog.apply(stroke, a.varyings.depth * weights[0] + b.varyings.depth * weights[1])
+            }
+            None => stroke,
+        }
+    };
+    let visible = |buf: &ScreenBuffer, x: u16, y: u16, t: f32| {
+        if !depth_test {
+            return true;
+        }
+        let stored = buf.depth_at(x, y);
+        let depth = a.depth + (b.depth - a.depth) * t;
+        // 1 - z / w is roughly proportional to 1 / distance, so this allows a relative bias
+        stored.is_infinite() || depth <= stored + (1.0 - stored) * LINE_DEPTH_BIAS
+    };
+
+    match settings.line_style {
+        LineStyle::Slope => {
+            let tex = slope_glyph(a.point, b.point);
+            let (x0, y0) = (a.point.x.floor() as i32, a.point.y.floor() as i32);
+            let (x1, y1) = (b.point.x.floor() as i32, b.point.y.floor() as i32);
+            bresenham(x0, y0, x1, y1, |x, y, t| {
+                if let Some((x, y)) = in_bounds(buf, x, y) && visible(buf, x, y, t) {
+                    set_line_pixel(buf, x, y, stroke_at(t, tex));
+                }
+            });
+        }
+        LineStyle::Braille => {
+            let (x0, y0) = ((a.point.x * 2.0).floor() as i32, (a.point.y * 4.0).floor() as i32);
+            let (x1, y1) = ((b.point.x * 2.0).floor() as i32, (b.point.y * 4.0).floor() as i32);
+            bresenham(x0, y0, x1, y1, |dx, dy, t| {
+                if let Some((x, y)) = in_bounds(buf, dx.div_euclid(2), dy.div_euclid(4)) && visible(buf, x, y, t) {
+                    // Dots from other lines in the same cell are kept
+                    let existing = buf.get_pixel(x, y).tex as u32;
+                    let bits = if (BRAILLE_BASE..BRAILLE_BASE + 0x100).contains(&existing) { existing - BRAILLE_BASE } else { 0 };
+                    let bits = bits | BRAILLE_DOTS[dx.rem_euclid(2) as usize][dy.rem_euclid(4) as usize];
+                    let tex = char::from_u32(BRAILLE_BASE + bits).unwrap();
+                    // Fog would swap the Braille glyph out, so only its colour is used
+                    set_line_pixel(buf, x, y, Stroke::new(stroke_at(t, tex).color, tex));
+                }
+            });
+        }
+    }
+}
+
+// Picks the glyph closest to the line's angle on screen, where cells are twice as tall as they are wide
+fn slope_glyph(a: Point, b: Point) -> char {
+    let dx = b.x - a.x;
+    let dy = (b.y - a.y) * 2.0;
+    let angle = dy.atan2(dx).to_degrees().rem_euclid(180.0);
+    match angle {
+        a if !(22.5..157.5).contains(&a) => '-',
+        a if a < 67.5 => '\\',
+        a if a < 112.5 => '|',
+        _ => '/',
+    }
+}
+
+fn in_bounds(buf: &ScreenBuffer, x: i32, y: i32) -> Option<(u16, u16)> {
+    if x < 0 || y < 0 || x >= buf.width as i32 || y >= buf.height as i32 {
+        return None;
+    }
+    Some((x as u16, y as u16))
+}
+
+// Calls plot for every cell on the line, with how far along the line it is
+fn bresenham<F: FnMut(i32, i32, f32)>(x0: i32, y0: i32, x1: i32, y1: i32, mut plot: F) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let steps = dx.max(-dy).max(1) as f32;
+
+    let (mut x, mut y) = (x0, y0);
+    let mut err = dx + dy;
+    let mut step = 0;
+    loop {
+        plot(x, y, step as f32 / steps);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::geometry::signed_area;
+    use crate::math::matrix::Matrix4;
+    use crate::math::vector::Vector;
+    use crate::rendering::frame_context::FrameContext;
+    use crate::rendering::lambert_shader::LambertShader;
+    use crate::rendering::point::Point;
+    use crate::rendering::render_mode::RenderMode;
+    use crate::rendering::render_settings::RenderSettings;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use crate::rendering::shading_mode::ShadingMode;
+    use crate::rendering::stroke::Stroke;
+    use crate::rendering::varyings::Varyings;
+    use super::{draw_line, draw_triangle, perspective_weights, ScreenLine, ScreenTriangle, ScreenVertex};
+
+    const UP: Vector = Vector { x: 0.0, y: 1.0, z: 0.0 };
+    const SIDE: Vector = Vector { x: 1.0, y: 0.0, z: 0.0 };
+    const LAMBERT: LambertShader = LambertShader { half: false };
+
+    fn vertex(x: f32, y: f32, normal: Vector) -> ScreenVertex {
+        ScreenVertex {
+            point: Point::new(x, y),
+            depth: 0.5,
+            inv_w: 1.0,
+            varyings: Varyings::new(Vector::new(x, y, 0.0), normal, [0.0; 2], 1.0),
+        }
+    }
+
+    fn triangle(verts: [ScreenVertex; 3]) -> ScreenTriangle<'static> {
+        ScreenTriangle { verts, face_normal: UP, stroke: Stroke::filled([200; 3]), texture: None, opacity: 1.0, shader: &LAMBERT, object_id: 0 }
+    }
+
+    fn draw(buf: &mut ScreenBuffer, tri: &ScreenTriangle, settings: &RenderSettings) {
+        let frame = FrameContext { shadow_maps: Vec::new(), eye: Vector::zero(), time: 0.0 };
+        draw_triangle(buf, tri, settings, &frame);
+    }
+
+    // Red channel down the left column of a triangle lit from above, whose bottom corner faces sideways
+    fn shaded_column(shading: ShadingMode) -> Vec<u8> {
+        let tri = triangle([vertex(0.0, 0.0, UP), vertex(16.0, 0.0, UP), vertex(0.0, 16.0, SIDE)]);
+        let settings = RenderSettings { shading, ..RenderSettings::new() };
+        let mut buf = ScreenBuffer::new(16, 16);
+        draw(&mut buf, &tri, &settings);
+        (0..16).map(|y| buf.get_pixel(0, y).color[0]).collect()
+    }
+
+    #[test]
+    fn smooth_shading_follows_the_vertex_normals() {
+        let flat = shaded_column(ShadingMode::Flat);
+        assert!(flat.iter().all(|&c| c == 200), "{:?}", flat);
+
+        let gouraud = shaded_column(ShadingMode::Gouraud);
+        let phong = shaded_column(ShadingMode::Phong);
+        for y in 0..15 {
+            assert!(gouraud[y] >= gouraud[y + 1] && phong[y] >= phong[y + 1], "{:?} {:?}", gouraud, phong);
+        }
+        // Half way down, colours are averaged but normals are renormalized first
+        let weight = 7.5 / 16.0;
+        assert!((gouraud[7] as f32 - 200.0 * (1.0 - weight)).abs() <= 1.0, "{:?}", gouraud);
+        let cos = (1.0 - weight) / Vector::new(weight, 1.0 - weight, 0.0).len();
+        assert!((phong[7] as f32 - 200.0 * cos).abs() <= 1.0, "{:?}", phong);
+    }
+
+    // A flat triangle over the top left of a 16 by 16 buffer, at one depth and in one colour
+    fn flat_at(depth: f32, color: [u8; 3]) -> ScreenTriangle<'static> {
+        let corner = |x: f32, y: f32| ScreenVertex { depth, ..vertex(x, y, UP) };
+        ScreenTriangle { stroke: Stroke::filled(color), ..triangle([corner(0.0, 0.0), corner(16.0, 0.0), corner(0.0, 16.0)]) }
+    }
+
+    // Across the buffer along the row at y = 4
+    fn line_at(depth: f32) -> ScreenLine {
+        let end = |x: f32| ScreenVertex { depth, ..vertex(x, 4.5, UP) };
+        ScreenLine { verts: [end(0.5), end(15.5)], stroke: Stroke::new([0, 0, 255], '-') }
+    }
+
+    #[test]
+    fn nearer_faces_win_whatever_the_draw_order() {
+        let settings = RenderSettings { lights: Vec::new(), ..RenderSettings::new() };
+        let (near, far) = (flat_at(0.2, [0, 255, 0]), flat_at(0.8, [255, 0, 0]));
+        for order in [[&near, &far], [&far, &near]] {
+            let mut buf = ScreenBuffer::new(16, 16);
+            for tri in order {
+                draw(&mut buf, tri, &settings);
+            }
+            assert_eq!(buf.get_pixel(2, 2).color, [0, 255, 0]);
+            assert_eq!(buf.depth_at(2, 2), 0.2);
+            assert!(buf.depth_at(15, 15).is_infinite());
+        }
+    }
+
+    #[test]
+    fn hidden_line_mode_hides_edges_behind_faces() {
+        let settings = RenderSettings { render_mode: RenderMode::HiddenLine, ..RenderSettings::new() };
+        let mut buf = ScreenBuffer::new(16, 16);
+        draw(&mut buf, &flat_at(0.5, [255, 0, 0]), &settings);
+        // Faces only fill the depth buffer
+        assert_eq!(buf.get_pixel(2, 2).tex, ' ');
+        assert_eq!(buf.depth_at(2, 2), 0.5);
+
+        draw_line(&mut buf, &line_at(0.9), &settings);
+        assert_eq!(buf.get_pixel(2, 4).tex, ' ');
+        // Past the face's hypotenuse nothing is in the way
+        assert_eq!(buf.get_pixel(14, 4).tex, '-');
+        draw_line(&mut buf, &line_at(0.3), &settings);
+        assert_eq!(buf.get_pixel(2, 4).tex, '-');
+
+        // Without faces every edge shows
+        let wireframe = RenderSettings { render_mode: RenderMode::Wireframe, ..RenderSettings::new() };
+        let mut buf = ScreenBuffer::new(16, 16);
+        buf.test_and_set_depth(2, 4, 0.1);
+        draw_line(&mut buf, &line_at(0.9), &wireframe);
+        assert_eq!(buf.get_pixel(2, 4).tex, '-');
+    }
+
+    #[test]
+    fn interpolation_is_perspective_correct() {
+        let projection = Matrix4::perspective(1.0, 1.0, 0.1, 100.0);
+        let project = |p: Vector| {
+            let [x, y, z, w] = projection.transform_point([p.x, p.y, p.z, 1.0]);
+            (Point::new(x / w, y / w), z / w, 1.0 / w)
+        };
+        // Leaning away from the camera, so one corner is five times as far as another
+        let corners = [Vector::new(-1.0, -1.0, 2.0), Vector::new(1.0, -1.0, 10.0), Vector::new(0.0, 1.0, 4.0)];
+        let verts = corners.map(|p| {
+            let (point, depth, inv_w) = project(p);
+            ScreenVertex { point, depth, inv_w, varyings: Varyings::new(p, UP, [0.0; 2], p.z) }
+        });
+
+        let target = (corners[0] + corners[1] * 2.0 + corners[2]) * 0.25;
+        let (point, depth, _) = project(target);
+        let [a, b, c] = verts.map(|v| v.point);
+        let area = signed_area(a, b, c);
+        let screen = [signed_area(b, c, point), signed_area(c, a, point), signed_area(a, b, point)].map(|e| e / area);
+
+        // Depth is affine on screen; everything else needs the 1 / w correction
+        let screen_depth: f32 = verts.iter().zip(screen).map(|(v, w)| v.depth * w).sum();
+        assert!((screen_depth - depth).abs() < 1e-5);
+        let corrected = Varyings::blend(&verts.map(|v| v.varyings), perspective_weights(&verts, screen));
+        assert!((corrected.position - target).len() < 1e-3, "{} != {}", corrected.position, target);
+        let affine = Varyings::blend(&verts.map(|v| v.varyings), screen);
+        assert!((affine.position - target).len() > 0.5);
+    }
+}
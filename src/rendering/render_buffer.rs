@@ -1,63 +1,496 @@
 use std::cmp::Ordering;
+use std::f32::consts::PI;
+use std::path::Path;
 use std::time::Duration;
 use crate::debug::debug_logger::log;
+use crate::interface::exporter::{export_meshes, ExportFormat};
 use crate::interface::input::{ActiveCommand, CommandType, InterpolationMode};
 use crate::math::euler_rotation::EulerRotation;
+use crate::math::matrix::Matrix4;
 use crate::math::mesh::Mesh;
-use crate::math::triangle::Triangle;
 use crate::math::projection_type::ProjectionType;
 use crate::math::quaternion::Quaternion;
+use crate::math::transform::Transform;
 use crate::math::vector::Vector;
 use crate::rendering::camera::Camera;
 use crate::rendering::object::Object;
-use crate::rendering::rasterizer::draw_triangle;
+use crate::rendering::outline::Outline;
+use crate::rendering::clipping::{clip_line, clip_triangle, ClipVertex};
+use crate::rendering::debug_view::{draw_depth, draw_overdraw, DebugView};
+use crate::rendering::frame_context::FrameContext;
+use crate::rendering::lambert_shader::LambertShader;
+use crate::rendering::rasterizer::{draw_line, draw_triangle, ScreenLine, ScreenTriangle, ScreenVertex};
+use crate::rendering::render_settings::RenderSettings;
+use crate::rendering::render_target::RenderTarget;
+use crate::rendering::sample_buffer::SampleBuffer;
 use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::shadow_map::ShadowMap;
+use crate::rendering::stroke::Stroke;
+use crate::rendering::transparency::Transparency;
+use crate::rendering::varyings::Varyings;
+use crate::rendering::weighted_blend::WeightedBlend;
+
+const FACE_NORMAL_COLOR: [u8; 3] = [255, 255, 0];
+const OBB_COLOR: [u8; 3] = [255, 0, 255];
+const AABB_COLOR: [u8; 3] = [0, 255, 255];
+const SELECTED_COLOR: [u8; 3] = [255, 160, 0];
 
 pub struct RenderBuffer {
     objs: Vec<Object>,
+    pub selected: usize,
+    // Time passed since the scene started, for shaders
+    time: Duration,
 }
 
 impl RenderBuffer {
     pub fn new() -> Self {
         RenderBuffer {
             objs: Vec::new(),
+            selected: 0,
+            time: Duration::ZERO,
         }
     }
-    pub fn add_mesh_worldspace(&mut self, obj: Object, camera: &Camera) {
+    pub fn add_mesh_worldspace(&mut self, obj: Object) -> usize {
         self.objs.push(obj);
+        self.objs.len() - 1
+    }
+    pub fn select(&mut self, obj_id: usize) -> Result<(), String> {
+        if obj_id >= self.objs.len() {
+            return Err(format!("no object with id {}", obj_id));
+        }
+        self.selected = obj_id;
+        Ok(())
     }
     pub fn add_command_to_obj(&mut self, cmd: ActiveCommand, obj_id: usize) {
-        self.objs[obj_id].active_commands.push(cmd);
+        if let Some(obj) = self.objs.get_mut(obj_id) {
+            obj.active_commands.push(cmd);
+        }
+    }
+    pub fn get_obj_mut(&mut self, obj_id: usize) -> Option<&mut Object> {
+        self.objs.get_mut(obj_id)
     }
     pub fn clear(&mut self) {
         self.objs.clear();
     }
-    pub fn order_tris_by_z(mut tris: Vec<Triangle>) -> Vec<Triangle> {
+    pub fn order_tris_by_z(mut tris: Vec<ScreenTriangle>) -> Vec<ScreenTriangle> {
         tris.sort_by(|t, o| {
-            if t.avg_z() > o.avg_z() {
+            if t.avg_depth() > o.avg_depth() {
                 return Ordering::Less
             }
-            else if t.avg_z() < o.avg_z() {
+            else if t.avg_depth() < o.avg_depth() {
                 return Ordering::Greater
             }
             Ordering::Equal
         });
         tris
     }
-    pub fn write_meshes_to_buffer(meshes: Vec<Mesh>, buffer: &mut ScreenBuffer, prj_type: &ProjectionType, camera: &Camera) {
-        let tris = meshes.iter().flat_map(|m| m.tris.iter().copied()).collect();
-        let tris = Self::order_tris_by_z(tris);
-        
-        for tri in tris {
-            draw_triangle(buffer, &tri, camera, prj_type);
+    // Each object's vertexes go through a single model-view-projection matrix into clip space, where
+    // triangles are clipped against the view volume before the perspective divide.
+    pub fn render(&mut self, buffer: &mut ScreenBuffer, prj_type: &ProjectionType, camera: &Camera, settings: &RenderSettings) {
+        let debug_settings;
+        let settings = match settings.debug_view {
+            Some(_) => {
+                debug_settings = settings.without_effects();
+                &debug_settings
+            }
+            None => settings,
+        };
+        let fog_settings;
+        let settings = match settings.fog {
+            Some(fog) if fog.color.is_none() => {
+                let background = match &settings.background {
+                    Some(background) => background.horizon(camera.rotation * Vector::new(0.0, 0.0, 1.0), settings.texture_filter),
+                    None => [0; 3],
+                };
+                fog_settings = RenderSettings { fog: Some(fog.resolved(background)), ..settings.clone() };
+                &fog_settings
+            }
+            _ => settings,
+        };
+        let debug_shader = settings.debug_view.and_then(|view| view.shader());
+        let view = camera.view_matrix();
+        let view_projection = camera.view_projection(prj_type);
+        let models = self.world_matrices();
+        if let Some(background) = &settings.background {
+            background.draw(buffer, camera, prj_type, settings.texture_filter);
+        }
+
+        let default_shader = LambertShader::new(true);
+        let mut tris = Vec::new();
+        let mut lines = Vec::new();
+        // Every face in world space, for rendering shadow maps
+        let mut casters = Vec::new();
+        if let Some(grid) = settings.grid {
+            for (segment, color) in grid.lines(Vector::from(camera.origin)) {
+                Self::add_world_line(&mut lines, segment, Stroke::new(color, ' '), &view, &view_projection, camera);
+            }
+        }
+        for (id, (obj, model)) in self.objs.iter().zip(models).enumerate() {
+            let mvp = view_projection * model;
+            let normal_matrix = model.normal_matrix();
+            let mesh = &obj.base_mesh;
+
+            let world: Vec<Vector> = mesh.vertexes.iter().map(|v| Vector::from(model.transform_vertex(*v))).collect();
+            let clip: Vec<[f32; 4]> = mesh.vertexes.iter().map(|v| mvp.transform_point([v.x, v.y, v.z, 1.0])).collect();
+            let model_view = view * model;
+            let depths: Vec<f32> = mesh.vertexes.iter().map(|v| model_view.transform_point([v.x, v.y, v.z, 1.0])[2]).collect();
+            let normals: Vec<Vector> = mesh.normals.iter().map(|n| normal_matrix.transform_vector(*n).normalized()).collect();
+
+            for face in mesh.faces.iter() {
+                let face_normal = normal_matrix.transform_vector(mesh.face_normal(face)).normalized();
+                let corner_normals = match face.normals {
+                    Some(indexes) => indexes.map(|i| normals[i]),
+                    None => [face_normal; 3],
+                };
+                let corner_uvs = match face.uvs {
+                    Some(indexes) => indexes.map(|i| mesh.uvs[i]),
+                    None => [[0.0; 2]; 3],
+                };
+                let corners = [0, 1, 2].map(|i| {
+                    let v = face.verts[i];
+                    ClipVertex::new(clip[v], Varyings::new(world[v], corner_normals[i], corner_uvs[i], depths[v]))
+                });
+                casters.push(face.verts.map(|v| world[v]));
+
+                let polygon: Vec<ScreenVertex> = clip_triangle(corners).iter()
+                    .map(|v| ScreenVertex::from_clip(v, camera))
+                    .collect();
+                for i in 1..polygon.len().saturating_sub(1) {
+                    tris.push(ScreenTriangle {
+                        verts: [polygon[0], polygon[i], polygon[i + 1]],
+                        face_normal,
+                        stroke: face.stroke.with_style(obj.style),
+                        texture: obj.texture.as_deref().filter(|_| face.uvs.is_some() && settings.debug_view.is_none()),
+                        opacity: obj.opacity,
+                        shader: debug_shader.or(obj.shader.as_deref()).unwrap_or(&default_shader),
+                        object_id: id,
+                    });
+                }
+            }
+
+            if let Some(length) = settings.face_normals {
+                for face in mesh.faces.iter() {
+                    let center = face.verts.iter().fold(Vector::zero(), |sum, &v| sum + world[v]) / 3.0;
+                    let normal = normal_matrix.transform_vector(mesh.face_normal(face)).normalized();
+                    let segment = [center, center + normal * length];
+                    Self::add_world_line(&mut lines, segment, Stroke::new(FACE_NORMAL_COLOR, ' '), &view, &view_projection, camera);
+                }
+            }
+
+            if settings.debug_view.is_some() {
+                let obb = obj.oriented_bounds(&model);
+                for segment in obb.edges() {
+                    Self::add_world_line(&mut lines, segment, Stroke::new(OBB_COLOR, ' '), &view, &view_projection, camera);
+                }
+                for segment in obj.world_bounds(&model).edges() {
+                    Self::add_world_line(&mut lines, segment, Stroke::new(AABB_COLOR, ' '), &view, &view_projection, camera);
+                }
+            }
+
+            let outline = settings.outline.filter(|_| settings.render_mode.draws_faces());
+            // Silhouette only, since a crease angle of pi never matches
+            let highlight = (settings.highlight_selected && id == self.selected).then(|| Outline::new(SELECTED_COLOR, PI));
+            if !settings.render_mode.draws_edges() && outline.is_none() && highlight.is_none() {
+                continue;
+            }
+            let edges = mesh.edges();
+            let mut add_line = |a: usize, b: usize, stroke: Stroke| {
+                let [a, b] = [a, b].map(|v| ClipVertex::new(clip[v], Varyings::new(world[v], Vector::zero(), [0.0; 2], depths[v])));
+                if let Some((a, b)) = clip_line(a, b) {
+                    lines.push(ScreenLine {
+                        verts: [a, b].map(|v| ScreenVertex::from_clip(&v, camera)),
+                        stroke,
+                    });
+                }
+            };
+            if settings.render_mode.draws_edges() {
+                for edge in edges.iter() {
+                    add_line(edge.verts[0], edge.verts[1], mesh.faces[edge.faces[0]].stroke);
+                }
+            }
+            // Outlines go after the plain edges so they're drawn on top
+            if let Some(outline) = outline {
+                for edge in outline.edges(mesh, edges, &clip) {
+                    add_line(edge.verts[0], edge.verts[1], Stroke::new(outline.color, ' '));
+                }
+            }
+            if let Some(highlight) = highlight {
+                for edge in highlight.edges(mesh, edges, &clip) {
+                    add_line(edge.verts[0], edge.verts[1], Stroke::new(highlight.color, ' '));
+                }
+            }
+        }
+
+        if settings.render_mode.draws_faces() {
+            let shadow_maps = match settings.shadows {
+                Some(shadows) => settings.lights.iter()
+                    .filter(|light| light.casts_shadows)
+                    .map(|light| ShadowMap::render(light, &shadows, &casters))
+                    .collect(),
+                None => Vec::new(),
+            };
+            let frame = FrameContext { shadow_maps, eye: Vector::from(camera.origin), time: self.time.as_secs_f32() };
+            if settings.debug_view == Some(DebugView::Overdraw) {
+                draw_overdraw(buffer, &tris);
+            }
+            else if settings.supersampling > 1 {
+                let factor = settings.supersampling;
+                let mut samples = SampleBuffer::new(buffer, factor);
+                let tris = tris.iter().map(|tri| tri.scaled(factor as f32)).collect();
+                Self::draw_faces(&mut samples, tris, settings, &frame);
+                samples.resolve(buffer, settings.coverage_glyphs);
+            }
+            else {
+                Self::draw_faces(buffer, tris, settings, &frame);
+            }
+            if settings.debug_view == Some(DebugView::Depth) {
+                draw_depth(buffer);
+            }
+        }
+        // Edges are depth tested against the faces unless the faces aren't drawn
+        for line in lines {
+            draw_line(buffer, &line, settings);
+        }
+        for pass in settings.post.iter() {
+            pass.apply(buffer);
+        }
+        if let Some(gizmo) = settings.axis_gizmo {
+            gizmo.draw(buffer, &view, settings);
+        }
+    }
+    // Clips a world space segment to the view and queues it for drawing
+    fn add_world_line(lines: &mut Vec<ScreenLine>, segment: [Vector; 2], stroke: Stroke, view: &Matrix4, view_projection: &Matrix4, camera: &Camera) {
+        let [a, b] = segment.map(|p| {
+            let p4 = [p.x, p.y, p.z, 1.0];
+            ClipVertex::new(view_projection.transform_point(p4), Varyings::new(p, Vector::zero(), [0.0; 2], view.transform_point(p4)[2]))
+        });
+        if let Some((a, b)) = clip_line(a, b) {
+            lines.push(ScreenLine {
+                verts: [a, b].map(|v| ScreenVertex::from_clip(&v, camera)),
+                stroke,
+            });
+        }
+    }
+    // Opaque faces go first, so the transparent ones can be blended over everything behind them
+    fn draw_faces<T: RenderTarget>(target: &mut T, tris: Vec<ScreenTriangle>, settings: &RenderSettings, frame: &FrameContext) {
+        let (opaque, transparent): (Vec<_>, Vec<_>) = tris.into_iter().partition(|tri| tri.opacity >= 1.0);
+        for tri in Self::order_tris_by_z(opaque) {
+            draw_triangle(target, &tri, settings, frame);
+        }
+        if transparent.is_empty() {
+            return;
+        }
+        match settings.transparency {
+            Transparency::Sorted => {
+                for tri in Self::order_tris_by_z(transparent) {
+                    draw_triangle(target, &tri, settings, frame);
+                }
+            }
+            Transparency::WeightedBlended => {
+                let mut blend = WeightedBlend::over(target);
+                for tri in transparent {
+                    draw_triangle(&mut blend, &tri, settings, frame);
+                }
+                blend.resolve(target);
+            }
         }
     }
     pub fn pass_obj_time(&mut self, time: Duration) {
+        self.time += time;
         for obj in self.objs.iter_mut() {
             obj.pass_time(time);
         }
     }
-    pub fn meshes_from_objects(&mut self) -> Vec<Mesh> {
-        self.objs.iter_mut().map(|obj| { obj.apply_commands() }).collect()
+    // Every object in world space as it was last drawn. Commands aren't advanced, so this can't change the animation.
+    pub fn meshes_from_objects(&self) -> Vec<Mesh> {
+        self.settled_matrices().iter().zip(&self.objs).map(|(m, obj)| obj.base_mesh.transform_matrix(m)).collect()
+    }
+    // World matrices from each object's transform as of the last frame, multiplied through its parents
+    fn settled_matrices(&self) -> Vec<Matrix4> {
+        (0..self.objs.len()).map(|id| {
+            let mut matrix = Matrix4::from_transform(&self.objs[id].current_transform());
+            let mut parent = self.objs[id].parent;
+            while let Some(p) = parent {
+                matrix = Matrix4::from_transform(&self.objs[p].current_transform()) * matrix;
+                parent = self.objs[p].parent;
+            }
+            matrix
+        }).collect()
+    }
+    // Parents come before their children, so world-frame commands can see the parent's transform
+    fn update_order(&self) -> Vec<usize> {
+        let depth = |mut id: usize| {
+            let mut depth = 0;
+            while let Some(p) = self.objs[id].parent {
+                depth += 1;
+                id = p;
+            }
+            depth
+        };
+        let mut order: Vec<usize> = (0..self.objs.len()).collect();
+        order.sort_by_key(|&id| depth(id));
+        order
+    }
+    // Advances every object's commands and multiplies its local matrix through its parents
+    pub fn world_matrices(&mut self) -> Vec<Matrix4> {
+        let mut matrices = vec![Matrix4::identity(); self.objs.len()];
+        let mut transforms = vec![Transform::identity(); self.objs.len()];
+        for id in self.update_order() {
+            let (parent_matrix, parent_transform) = match self.objs[id].parent {
+                Some(p) => (matrices[p], transforms[p]),
+                None => (Matrix4::identity(), Transform::identity()),
+            };
+            let local = self.objs[id].apply_commands(&parent_transform);
+            matrices[id] = parent_matrix * Matrix4::from_transform(&local);
+            transforms[id] = parent_transform.compose(&local);
+        }
+        matrices
+    }
+    fn compose_with_parents(&self, obj_id: usize, locals: &[Transform]) -> Transform {
+        let mut world = locals[obj_id];
+        let mut parent = self.objs[obj_id].parent;
+        while let Some(p) = parent {
+            world = locals[p].compose(&world);
+            parent = self.objs[p].parent;
+        }
+        world
+    }
+    pub fn local_and_world(&self, obj_id: usize) -> Result<(Transform, Transform), String> {
+        if obj_id >= self.objs.len() {
+            return Err(format!("no object with id {}", obj_id));
+        }
+        let locals: Vec<Transform> = self.objs.iter().map(|obj| obj.current_transform()).collect();
+        Ok((locals[obj_id], self.compose_with_parents(obj_id, &locals)))
+    }
+    // Reparents an object while keeping its settled world transform, so it doesn't jump on screen
+    pub fn set_parent(&mut self, obj_id: usize, parent: Option<usize>) -> Result<(), String> {
+        for id in [Some(obj_id), parent].into_iter().flatten() {
+            if id >= self.objs.len() {
+                return Err(format!("no object with id {}", id));
+            }
+        }
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            if a == obj_id {
+                return Err(format!("object {} can't be parented to its own descendant", obj_id));
+            }
+            ancestor = self.objs[a].parent;
+        }
+
+        let settled: Vec<Transform> = self.objs.iter().map(|obj| obj.transform).collect();
+        let world = self.compose_with_parents(obj_id, &settled);
+        let local = match parent {
+            Some(p) => world.relative_to(&self.compose_with_parents(p, &settled)),
+            None => world.with_pivot(settled[obj_id].pivot),
+        };
+
+        let obj = &mut self.objs[obj_id];
+        obj.parent = parent;
+        obj.transform = local;
+        Ok(())
+    }
+    pub fn export(&self, format: ExportFormat, path: &Path, obj_id: Option<usize>) -> Result<(), String> {
+        let meshes = match obj_id {
+            Some(id) => match self.meshes_from_objects().into_iter().nth(id) {
+                Some(mesh) => vec![mesh],
+                None => return Err(format!("no object with id {}", id)),
+            },
+            None => self.meshes_from_objects(),
+        };
+        export_meshes(&meshes, path, format)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::interface::input::{ActiveCommand, CommandType, Frame, InterpolationMode};
+    use crate::math::mesh::Mesh;
+    use crate::math::quaternion::Quaternion;
+    use crate::math::vector::Vector;
+    use crate::rendering::object::Object;
+    use super::RenderBuffer;
+
+    fn placed(position: Vector) -> Object {
+        let mut obj = Object::new(Mesh::cuboid(1.0, 1.0, 1.0));
+        obj.set_position(position);
+        obj
+    }
+
+    fn first_vertex(buffer: &RenderBuffer, id: usize) -> Vector {
+        Vector::from(buffer.meshes_from_objects()[id].vertexes[0])
+    }
+
+    #[test]
+    fn children_follow_their_parents() {
+        let mut buffer = RenderBuffer::new();
+        let parent = buffer.add_mesh_worldspace(placed(Vector::new(10.0, 0.0, 0.0)));
+        let child = buffer.add_mesh_worldspace(placed(Vector::zero()));
+        buffer.set_parent(child, Some(parent)).unwrap();
+        buffer.get_obj_mut(child).unwrap().set_position(Vector::new(0.0, 5.0, 0.0));
+        buffer.get_obj_mut(parent).unwrap().set_rotation(Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2));
+        buffer.world_matrices();
+
+        // The child's offset turns with the parent, from +y to -x
+        let corner = first_vertex(&buffer, child);
+        let expected = Vector::new(10.0 - 4.5, -0.5, -0.5);
+        assert!((corner - expected).len() < 1e-4, "{} != {}", corner, expected);
+    }
+
+    #[test]
+    fn exporting_leaves_commands_running() {
+        let mut buffer = RenderBuffer::new();
+        let id = buffer.add_mesh_worldspace(placed(Vector::zero()));
+        let spin = ActiveCommand {
+            command: CommandType::Rotate { delta: Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), 1.0), frame: Frame::Local },
+            interpolation: InterpolationMode::Continuous,
+            time_passed: Duration::ZERO,
+        };
+        let step = ActiveCommand {
+            command: CommandType::Move { delta: Vector::new(1.0, 0.0, 0.0), frame: Frame::World },
+            interpolation: InterpolationMode::Instant,
+            time_passed: Duration::ZERO,
+        };
+        buffer.add_command_to_obj(spin, id);
+        buffer.pass_obj_time(Duration::from_millis(500));
+        buffer.world_matrices();
+        buffer.add_command_to_obj(step, id);
+        buffer.pass_obj_time(Duration::from_millis(500));
+
+        let before = first_vertex(&buffer, id);
+        let again = first_vertex(&buffer, id);
+        assert!((before - again).len() < 1e-6);
+        let obj = buffer.get_obj_mut(id).unwrap();
+        assert_eq!(obj.active_commands.len(), 2);
+        assert!(obj.transform.translation.len() < 1e-6);
+    }
+
+    #[test]
+    fn reparenting_keeps_objects_in_place() {
+        let mut buffer = RenderBuffer::new();
+        let parent = buffer.add_mesh_worldspace(placed(Vector::new(10.0, 0.0, 0.0)));
+        let child = buffer.add_mesh_worldspace(placed(Vector::new(0.0, 5.0, 0.0)));
+        buffer.get_obj_mut(parent).unwrap().set_scale(Vector::new(2.0, 2.0, 2.0));
+        let before = first_vertex(&buffer, child);
+
+        buffer.set_parent(child, Some(parent)).unwrap();
+        assert!((first_vertex(&buffer, child) - before).len() < 1e-4);
+        let (local, world) = buffer.local_and_world(child).unwrap();
+        assert!((local.translation - Vector::new(-5.0, 2.5, 0.0)).len() < 1e-4);
+        assert!((world.translation - Vector::new(0.0, 5.0, 0.0)).len() < 1e-4);
+
+        buffer.set_parent(child, None).unwrap();
+        assert!((first_vertex(&buffer, child) - before).len() < 1e-4);
+    }
+
+    #[test]
+    fn parents_cannot_be_their_own_descendants() {
+        let mut buffer = RenderBuffer::new();
+        let ids: Vec<usize> = (0..3).map(|_| buffer.add_mesh_worldspace(placed(Vector::zero()))).collect();
+        buffer.set_parent(ids[1], Some(ids[0])).unwrap();
+        buffer.set_parent(ids[2], Some(ids[1])).unwrap();
+        assert!(buffer.set_parent(ids[0], Some(ids[2])).is_err());
+        assert!(buffer.set_parent(ids[0], Some(ids[0])).is_err());
+        assert!(buffer.set_parent(ids[0], Some(3)).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    Solid,
+    Wireframe,
+    WireframeOverSolid,
+    // Only the edges a solid render would show, found by drawing the faces into the depth buffer
+    HiddenLine,
+}
+
+impl RenderMode {
+    pub fn draws_faces(&self) -> bool {
+        !matches!(self, RenderMode::Wireframe)
+    }
+    pub fn draws_edges(&self) -> bool {
+        !matches!(self, RenderMode::Solid)
+    }
+}
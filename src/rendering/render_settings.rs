@@ -0,0 +1,81 @@
+use std::rc::Rc;
+use crate::math::vector::Vector;
+use crate::rendering::axis_gizmo::AxisGizmo;
+use crate::rendering::background::Background;
+use crate::rendering::debug_view::DebugView;
+use crate::rendering::directional_light::DirectionalLight;
+use crate::rendering::fill_mode::FillMode;
+use crate::rendering::fog::Fog;
+use crate::rendering::grid::Grid;
+use crate::rendering::line_style::LineStyle;
+use crate::rendering::outline::Outline;
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::render_mode::RenderMode;
+use crate::rendering::shading_mode::ShadingMode;
+use crate::rendering::shadows::Shadows;
+use crate::rendering::texture_filter::TextureFilter;
+use crate::rendering::transparency::Transparency;
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub shading: ShadingMode,
+    pub render_mode: RenderMode,
+    pub line_style: LineStyle,
+    pub outline: Option<Outline>,
+    pub texture_filter: TextureFilter,
+    // Samples per cell along each axis, 1 for none
+    pub supersampling: u16,
+    pub coverage_glyphs: bool,
+    pub fog: Option<Fog>,
+    pub lights: Vec<DirectionalLight>,
+    pub shadows: Option<Shadows>,
+    pub transparency: Transparency,
+    // None leaves the terminal's own background
+    pub background: Option<Background>,
+    pub fill: FillMode,
+    // Run over the finished frame in order
+    pub post: Vec<Rc<dyn PostProcess>>,
+    pub debug_view: Option<DebugView>,
+    // Length of the face normal lines drawn over the scene, in world units
+    pub face_normals: Option<f32>,
+    pub grid: Option<Grid>,
+    pub axis_gizmo: Option<AxisGizmo>,
+    // Outlines the object commands act on
+    pub highlight_selected: bool,
+}
+
+impl RenderSettings {
+    pub fn new() -> Self {
+        Self {
+            shading: ShadingMode::Flat,
+            render_mode: RenderMode::Solid,
+            line_style: LineStyle::Slope,
+            outline: None,
+            texture_filter: TextureFilter::Bilinear,
+            supersampling: 1,
+            coverage_glyphs: false,
+            fog: None,
+            // Straight down, matching how scenes were lit before lights could be set
+            lights: vec![DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), true)],
+            shadows: None,
+            transparency: Transparency::Sorted,
+            background: None,
+            fill: FillMode::Glyph,
+            post: Vec::new(),
+            debug_view: None,
+            face_normals: None,
+            grid: None,
+            axis_gizmo: None,
+            highlight_selected: true,
+        }
+    }
+    // A copy without the effects that would muddy a debug view
+    pub fn without_effects(&self) -> Self {
+        Self {
+            shadows: None,
+            fog: None,
+            post: Vec::new(),
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use crate::rendering::stroke::Stroke;
+
+// Anything triangles can be rasterized into: the screen itself, or a finer grid of samples
+pub trait RenderTarget {
+    fn width(&self) -> u16;
+    fn height(&self) -> u16;
+    fn depth_at(&self, x: u16, y: u16) -> f32;
+    // Records the depth if it's at least as near as what's there, and returns whether it was
+    fn test_and_set_depth(&mut self, x: u16, y: u16, depth: f32) -> bool;
+    // Depth test without recording, for surfaces that don't hide what's behind them
+    fn test_depth(&self, x: u16, y: u16, depth: f32) -> bool {
+        depth <= self.depth_at(x, y)
+    }
+    fn set_pixel(&mut self, x: u16, y: u16, s: Stroke);
+    // Mixes a stroke over what's there at the given opacity. depth is for targets that weight by it.
+    fn blend_pixel(&mut self, x: u16, y: u16, s: Stroke, alpha: f32, depth: f32);
+}
@@ -0,0 +1,178 @@
+use crate::rendering::render_target::RenderTarget;
+use crate::rendering::screen_buffer::ScreenBuffer;
+use crate::rendering::stroke::Stroke;
+
+// factor x factor samples per cell, resolved down to the screen once everything is drawn
+pub struct SampleBuffer {
+    width: u16,
+    height: u16,
+    factor: u16,
+    samples: Vec<Option<Stroke>>,
+    depth: Vec<f32>,
+    // The screen's background per cell, which shows through uncovered samples
+    background: Vec<[u8; 3]>,
+}
+
+impl SampleBuffer {
+    pub fn new(screen: &ScreenBuffer, factor: u16) -> Self {
+        let (width, height) = (screen.width * factor, screen.height * factor);
+        let size = width as usize * height as usize;
+        let mut background = Vec::with_capacity(screen.width as usize * screen.height as usize);
+        for y in 0..screen.height {
+            for x in 0..screen.width {
+                background.push(screen.background_at(x, y));
+            }
+        }
+        Self {
+            width,
+            height,
+            factor,
+            samples: vec![None; size],
+            depth: vec![f32::INFINITY; size],
+            background,
+        }
+    }
+    fn background_at(&self, x: u16, y: u16) -> [u8; 3] {
+        let cells_x = (self.width / self.factor) as usize;
+        self.background[(y / self.factor) as usize * cells_x + (x / self.factor) as usize]
+    }
+    fn index_of(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+    // Averages each cell's samples. Uncovered samples count as the background, unless coverage glyphs are on,
+    // in which case the covered samples are averaged and the glyph shows how much of the cell is filled.
+    // Cells take the depth of their nearest sample, so lines can still be tested against them.
+    pub fn resolve(&self, buf: &mut ScreenBuffer, coverage_glyphs: bool) {
+        let total = (self.factor * self.factor) as f32;
+        for y in 0..buf.height.min(self.height / self.factor) {
+            for x in 0..buf.width.min(self.width / self.factor) {
+                let mut covered = 0;
+                let mut sum = [0.0; 3];
+                let mut uncovered_sum = [0.0; 3];
+                let mut depth = f32::INFINITY;
+                // Any covered sample, as the template for the cell's glyph and style
+                let mut template = None;
+                for sy in y * self.factor..(y + 1) * self.factor {
+                    for sx in x * self.factor..(x + 1) * self.factor {
+                        let i = self.index_of(sx, sy);
+                        depth = depth.min(self.depth[i]);
+                        if let Some(s) = self.samples[i] {
+                            covered += 1;
+                            for (acc, c) in sum.iter_mut().zip(s.visible_color().unwrap_or(s.color)) {
+                                *acc += c as f32;
+                            }
+                            template = Some(s);
+                        }
+                        else {
+                            for (acc, c) in uncovered_sum.iter_mut().zip(self.background_at(sx, sy)) {
+                                *acc += c as f32;
+                            }
+                        }
+                    }
+                }
+                if depth.is_finite() {
+                    buf.test_and_set_depth(x, y, depth);
+                }
+                let Some(template) = template else {
+                    continue;
+                };
+
+                let coverage = covered as f32 / total;
+                let (sum, divisor, tex) = if coverage_glyphs {
+                    (sum, covered as f32, coverage_glyph(coverage, template.tex))
+                } else {
+                    ([0, 1, 2].map(|i| sum[i] + uncovered_sum[i]), total, template.tex)
+                };
+                let color = sum.map(|c| (c / divisor).round().clamp(0.0, 255.0) as u8);
+                // A partly covered filled cell drops its background so the coverage glyph shows
+                let stroke = if tex == template.tex { template } else { Stroke { tex, bg: None, ..template } };
+                buf.set_pixel(x, y, stroke.with_color(color));
+            }
+        }
+    }
+}
+
+fn coverage_glyph(coverage: f32, full: char) -> char {
+    match coverage {
+        c if c >= 1.0 => full,
+        c if c > 0.6 => '▓',
+        c if c > 0.3 => '▒',
+        _ => '░',
+    }
+}
+
+impl RenderTarget for SampleBuffer {
+    fn width(&self) -> u16 {
+        self.width
+    }
+    fn height(&self) -> u16 {
+        self.height
+    }
+    fn depth_at(&self, x: u16, y: u16) -> f32 {
+        self.depth[self.index_of(x, y)]
+    }
+    fn test_and_set_depth(&mut self, x: u16, y: u16, depth: f32) -> bool {
+        let i = self.index_of(x, y);
+        if depth > self.depth[i] {
+            return false;
+        }
+        self.depth[i] = depth;
+        true
+    }
+    fn set_pixel(&mut self, x: u16, y: u16, s: Stroke) {
+        let i = self.index_of(x, y);
+        self.samples[i] = Some(s);
+    }
+    fn blend_pixel(&mut self, x: u16, y: u16, s: Stroke, alpha: f32, _depth: f32) {
+        let i = self.index_of(x, y);
+        let under = self.samples[i].and_then(|under| under.visible_color().map(|color| (under, color)));
+        self.samples[i] = Some(match under {
+            None => s.over(self.background_at(x, y), alpha),
+            Some((_, color)) if alpha >= 0.5 => s.over(color, alpha),
+            Some((under, color)) => under.with_color(s.over(color, alpha).color),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::render_target::RenderTarget;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use crate::rendering::stroke::Stroke;
+    use super::SampleBuffer;
+
+    // Two cells, the first on a blue background and half covered in white, the second fully red
+    fn half_covered() -> (ScreenBuffer, SampleBuffer) {
+        let mut screen = ScreenBuffer::new(2, 1);
+        screen.set_background(0, 0, [0, 0, 100]);
+        let mut samples = SampleBuffer::new(&screen, 2);
+        samples.test_and_set_depth(0, 0, 0.5);
+        samples.set_pixel(0, 0, Stroke::new([255; 3], '#'));
+        samples.test_and_set_depth(1, 1, 0.3);
+        samples.set_pixel(1, 1, Stroke::new([255; 3], '#'));
+        for (x, y) in [(2, 0), (3, 0), (2, 1), (3, 1)] {
+            samples.set_pixel(x, y, Stroke::new([255, 0, 0], '#'));
+        }
+        (screen, samples)
+    }
+
+    #[test]
+    fn resolve_averages_samples_with_the_background() {
+        let (mut screen, samples) = half_covered();
+        samples.resolve(&mut screen, false);
+        let edge = screen.get_pixel(0, 0);
+        assert_eq!((edge.color, edge.tex), ([128, 128, 178], '#'));
+        // The cell keeps its nearest sample's depth
+        assert_eq!(screen.depth_at(0, 0), 0.3);
+        assert_eq!(screen.get_pixel(1, 0).color, [255, 0, 0]);
+    }
+
+    #[test]
+    fn coverage_glyphs_show_how_much_is_filled() {
+        let (mut screen, samples) = half_covered();
+        samples.resolve(&mut screen, true);
+        let edge = screen.get_pixel(0, 0);
+        assert_eq!((edge.color, edge.tex), ([255; 3], '▒'));
+        assert_eq!(screen.get_pixel(1, 0).tex, '#');
+    }
+}
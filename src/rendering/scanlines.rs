@@ -0,0 +1,26 @@
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::screen_buffer::ScreenBuffer;
+
+// Darkens every other row, like the gaps between a CRT's scanlines
+#[derive(Debug, Clone, Copy)]
+pub struct Scanlines {
+    // How much darker the odd rows get, 0..1
+    pub strength: f32,
+}
+
+impl Scanlines {
+    pub fn new(strength: f32) -> Self {
+        Self { strength: strength.clamp(0.0, 1.0) }
+    }
+}
+
+impl PostProcess for Scanlines {
+    fn apply(&self, buf: &mut ScreenBuffer) {
+        let scale = 1.0 - self.strength;
+        for y in (1..buf.height).step_by(2) {
+            for x in 0..buf.width {
+                buf.map_cell_colors(x, y, |color| color.map(|c| (c as f32 * scale).round() as u8));
+            }
+        }
+    }
+}
@@ -3,12 +3,18 @@ use termion::color;
 use termion::color::{Color, Fg};
 use termion::cursor::Goto;
 use crate::rendering::point::Point;
+use crate::rendering::render_target::RenderTarget;
 use crate::rendering::stroke::Stroke;
+use crate::rendering::text_style::TextStyle;
 
 pub struct ScreenBuffer {
     pub width: u16,
     pub height: u16,
     buffer: Vec<Stroke>,
+    // Depth of the nearest surface drawn into each cell, infinite where there is none
+    depth: Vec<f32>,
+    // Cell background colours, or the terminal's own where None
+    background: Vec<Option<[u8; 3]>>,
 }
 
 impl ScreenBuffer {
@@ -17,7 +23,9 @@ impl ScreenBuffer {
         Self {
             width,
             height,
-            buffer: vec![Stroke::new([255, 255, 255], ' '); size]
+            buffer: vec![Stroke::new([255, 255, 255], ' '); size],
+            depth: vec![f32::INFINITY; size],
+            background: vec![None; size],
         }
     }
 
@@ -29,6 +37,56 @@ impl ScreenBuffer {
         let i = self.index_of(x, y);
         self.buffer[i] = s;
     }
+
+    pub fn get_pixel(&self, x: u16, y: u16) -> Stroke {
+        self.buffer[self.index_of(x, y)]
+    }
+
+    pub fn depth_at(&self, x: u16, y: u16) -> f32 {
+        self.depth[self.index_of(x, y)]
+    }
+
+    pub fn set_background(&mut self, x: u16, y: u16, color: [u8; 3]) {
+        let i = self.index_of(x, y);
+        self.background[i] = Some(color);
+    }
+
+    // Black stands in for the terminal's background, which can't be known
+    pub fn background_at(&self, x: u16, y: u16) -> [u8; 3] {
+        self.background[self.index_of(x, y)].unwrap_or([0; 3])
+    }
+
+    // Records the depth if it's at least as near as the cell's, and returns whether it was
+    pub fn test_and_set_depth(&mut self, x: u16, y: u16, depth: f32) -> bool {
+        let i = self.index_of(x, y);
+        if depth > self.depth[i] {
+            return false;
+        }
+        self.depth[i] = depth;
+        true
+    }
+
+    // What the cell mostly shows: its glyph's colour, or the background behind a blank
+    pub fn visible_color(&self, x: u16, y: u16) -> [u8; 3] {
+        self.get_pixel(x, y).visible_color().unwrap_or_else(|| self.background_at(x, y))
+    }
+
+    // Changes every colour in the cell: the glyph's, its background and the scene's background
+    pub fn map_cell_colors<F: FnMut([u8; 3]) -> [u8; 3]>(&mut self, x: u16, y: u16, mut f: F) {
+        let i = self.index_of(x, y);
+        let s = &mut self.buffer[i];
+        s.color = f(s.color);
+        s.bg = s.bg.map(&mut f);
+        self.background[i] = self.background[i].map(&mut f);
+    }
+
+    pub fn map_colors<F: FnMut([u8; 3]) -> [u8; 3]>(&mut self, mut f: F) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.map_cell_colors(x, y, &mut f);
+            }
+        }
+    }
     
     pub fn fill_string(&mut self, s: &str, p: Point) {
         let cs = s.chars().collect::<Vec<_>>();
@@ -44,20 +102,118 @@ impl ScreenBuffer {
 
     pub fn clear(&mut self) {
         self.buffer.fill(Stroke::new([255, 255, 255], ' '));
+        self.depth.fill(f32::INFINITY);
+        self.background.fill(None);
     }
 
     pub fn write<W: Write>(&self, w: &mut W) {
         let mut output = String::with_capacity(((self.width + 1) * self.height + 8) as usize);
         output.push_str("\x1B[1;1H");
+        // Background colours and styles are only sent when they change, so a frame without any is unaffected
+        let mut background = None;
+        let mut style = TextStyle::default();
         for y in 0..self.height {
             for x in 0..self.width {
-                let s = self.buffer[self.index_of(x, y)];
+                let i = self.index_of(x, y);
+                let s = self.buffer[i];
+                let cell_background = s.bg.or(self.background[i]);
+                if cell_background != background {
+                    background = cell_background;
+                    match background {
+                        Some([r, g, b]) => output.push_str(&format!("\x1b[48;2;{};{};{}m", r, g, b)),
+                        None => output.push_str("\x1b[49m"),
+                    }
+                }
+                if s.style != style {
+                    if style != TextStyle::default() {
+                        output.push_str("\x1b[22;27m");
+                    }
+                    style = s.style;
+                    output.push_str(&style.codes());
+                }
                 output.push_str(&format!("{}{}", s.as_str(), Goto(x + 1, y + 1)));
                 output.push(s.tex)
             }
         }
+        if background.is_some() {
+            output.push_str("\x1b[49m");
+        }
+        if style != TextStyle::default() {
+            output.push_str("\x1b[22;27m");
+        }
 
         w.write_all(output.as_bytes()).unwrap();
         w.flush().unwrap();
     }
-}
\ No newline at end of file
+}
+
+impl RenderTarget for ScreenBuffer {
+    fn width(&self) -> u16 {
+        self.width
+    }
+    fn height(&self) -> u16 {
+        self.height
+    }
+    fn depth_at(&self, x: u16, y: u16) -> f32 {
+        ScreenBuffer::depth_at(self, x, y)
+    }
+    fn test_and_set_depth(&mut self, x: u16, y: u16, depth: f32) -> bool {
+        ScreenBuffer::test_and_set_depth(self, x, y, depth)
+    }
+    fn set_pixel(&mut self, x: u16, y: u16, s: Stroke) {
+        ScreenBuffer::set_pixel(self, x, y, s)
+    }
+    // Blank cells show the background. The glyph is whichever of the two strokes shows more.
+    fn blend_pixel(&mut self, x: u16, y: u16, s: Stroke, alpha: f32, _depth: f32) {
+        let under = self.get_pixel(x, y);
+        let blended = match under.visible_color() {
+            None => s.over(self.background_at(x, y), alpha),
+            Some(color) if alpha >= 0.5 => s.over(color, alpha),
+            Some(color) => under.with_color(s.over(color, alpha).color),
+        };
+        ScreenBuffer::set_pixel(self, x, y, blended)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::rendering::stroke::Stroke;
+    use super::ScreenBuffer;
+
+    fn output(buf: &ScreenBuffer) -> String {
+        let mut bytes = Vec::new();
+        buf.write(&mut bytes);
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn backgrounds_are_only_sent_when_they_change() {
+        let mut buf = ScreenBuffer::new(3, 1);
+        buf.set_pixel(0, 0, Stroke::new([255; 3], '#'));
+        assert!(!output(&buf).contains("\x1b[48;2"));
+        assert!(!output(&buf).contains("\x1b[49m"));
+
+        for x in 0..3 {
+            buf.set_background(x, 0, [0, 0, 90]);
+        }
+        // A filled stroke's own background wins over the scene's
+        buf.set_pixel(2, 0, Stroke::filled([90, 0, 0]));
+        let text = output(&buf);
+        assert_eq!(text.matches("\x1b[48;2;0;0;90m").count(), 1);
+        assert_eq!(text.matches("\x1b[48;2;90;0;0m").count(), 1);
+        assert!(text.ends_with(" \x1b[49m"));
+    }
+
+    #[test]
+    fn blank_cells_show_their_background() {
+        let mut buf = ScreenBuffer::new(2, 1);
+        buf.set_background(0, 0, [10, 20, 30]);
+        buf.set_pixel(1, 0, Stroke::new([255; 3], '#').with_background([1, 1, 1]));
+        assert_eq!(buf.visible_color(0, 0), [10, 20, 30]);
+        assert_eq!(buf.visible_color(1, 0), [255; 3]);
+
+        buf.map_colors(|[r, g, b]| [b, g, r]);
+        assert_eq!(buf.background_at(0, 0), [30, 20, 10]);
+        assert_eq!(buf.get_pixel(1, 0).bg, Some([1, 1, 1]));
+        assert!(buf.get_pixel(0, 0).bg.is_none());
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::stroke::Stroke;
+
+// Works out what a surface looks like at a point. Textures, shadows and fog are applied afterwards.
+pub trait Shader: Debug {
+    fn shade(&self, input: &FragmentInput) -> Stroke;
+    // Shaders that show data rather than light turn this off so shadows don't darken them
+    fn receives_shadows(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::vector::Vector;
+    use crate::rendering::blinn_phong_shader::BlinnPhongShader;
+    use crate::rendering::depth_shader::DepthShader;
+    use crate::rendering::directional_light::DirectionalLight;
+    use crate::rendering::flat_shader::FlatShader;
+    use crate::rendering::fragment_input::FragmentInput;
+    use crate::rendering::lambert_shader::LambertShader;
+    use crate::rendering::normal_shader::NormalShader;
+    use crate::rendering::object_id_shader::ObjectIdShader;
+    use crate::rendering::pulse_shader::PulseShader;
+    use crate::rendering::stroke::Stroke;
+    use crate::rendering::toon_shader::ToonShader;
+    use crate::rendering::uv_shader::UvShader;
+    use super::Shader;
+
+    const BASE: [u8; 3] = [200, 100, 50];
+    const UP: Vector = Vector { x: 0.0, y: 1.0, z: 0.0 };
+    const SIDE: Vector = Vector { x: 1.0, y: 0.0, z: 0.0 };
+    const DOWN: Vector = Vector { x: 0.0, y: -1.0, z: 0.0 };
+
+    // A point at the origin seen from straight above
+    fn input(normal: Vector, lights: &[DirectionalLight]) -> FragmentInput<'_> {
+        FragmentInput {
+            position: Vector::zero(),
+            normal,
+            uv: [0.0, 0.0],
+            depth: 10.0,
+            object_id: 0,
+            time: 0.0,
+            base: Stroke::filled(BASE),
+            lights,
+            eye: Vector::new(0.0, 10.0, 0.0),
+        }
+    }
+
+    fn shade(shader: &dyn Shader, normal: Vector, lights: &[DirectionalLight]) -> [u8; 3] {
+        shader.shade(&input(normal, lights)).color
+    }
+
+    // Shining straight down
+    fn sun() -> [DirectionalLight; 1] {
+        [DirectionalLight::new(DOWN, false)]
+    }
+
+    #[test]
+    fn flat_ignores_the_light() {
+        assert_eq!(shade(&FlatShader, DOWN, &sun()), BASE);
+    }
+
+    #[test]
+    fn lambert_follows_the_cosine() {
+        let full = LambertShader::new(false);
+        assert_eq!(shade(&full, UP, &sun()), BASE);
+        assert_eq!(shade(&full, SIDE, &sun()), [0, 0, 0]);
+        assert_eq!(shade(&full, DOWN, &sun()), [0, 0, 0]);
+        // Averaged over the lights, and unlit without any
+        let both = [DirectionalLight::new(DOWN, false), DirectionalLight::new(UP, false)];
+        assert_eq!(shade(&full, UP, &both), [100, 50, 25]);
+        assert_eq!(shade(&full, SIDE, &[]), BASE);
+
+        let half = LambertShader::new(true);
+        assert_eq!(shade(&half, UP, &sun()), BASE);
+        assert_eq!(shade(&half, SIDE, &sun()), [100, 50, 25]);
+        assert_eq!(shade(&half, DOWN, &sun()), [0, 0, 0]);
+    }
+
+    #[test]
+    fn blinn_phong_adds_a_highlight_facing_the_light() {
+        let shader = BlinnPhongShader::new(0.2, 0.5, 32.0);
+        // Light, eye and normal line up, so the highlight is at full strength
+        assert_eq!(shade(&shader, UP, &sun()), [255, 228, 178]);
+        assert_eq!(shade(&shader, DOWN, &sun()), [40, 20, 10]);
+    }
+
+    #[test]
+    fn toon_snaps_to_bands() {
+        let shader = ToonShader::new(3);
+        assert_eq!(shade(&shader, UP, &sun()), BASE);
+        assert_eq!(shade(&shader, SIDE, &sun()), [133, 67, 33]);
+        assert_eq!(shade(&shader, DOWN, &sun()), [67, 33, 17]);
+    }
+
+    #[test]
+    fn normal_maps_axes_to_channels() {
+        assert_eq!(shade(&NormalShader, UP, &sun()), [128, 255, 128]);
+        assert_eq!(shade(&NormalShader, -SIDE, &sun()), [0, 128, 128]);
+        assert!(!NormalShader.receives_shadows());
+    }
+
+    #[test]
+    fn depth_fades_out_to_max() {
+        let shader = DepthShader::new(20.0);
+        let grey = |depth: f32| shader.shade(&FragmentInput { depth, ..input(UP, &[]) }).color;
+        assert_eq!(grey(0.0), [255; 3]);
+        assert_eq!(grey(10.0), [128; 3]);
+        assert_eq!(grey(30.0), [0; 3]);
+    }
+
+    #[test]
+    fn uv_repeats_like_a_texture() {
+        let color = UvShader.shade(&FragmentInput { uv: [0.25, 1.5], ..input(UP, &[]) }).color;
+        assert_eq!(color, [64, 128, 0]);
+    }
+
+    #[test]
+    fn object_ids_get_distinct_colours() {
+        let color = |object_id: usize| ObjectIdShader.shade(&FragmentInput { object_id, ..input(UP, &sun()) }).color;
+        assert_eq!(color(0), [255, 55, 55]);
+        for id in 1..16 {
+            assert_ne!(color(id), color(id - 1));
+        }
+    }
+
+    #[test]
+    fn pulse_peaks_half_way_through_each_period() {
+        let shader = PulseShader::new([0, 0, 255], 2.0);
+        let color = |time: f32| shader.shade(&FragmentInput { time, ..input(UP, &sun()) }).color;
+        assert_eq!(color(0.0), BASE);
+        assert_eq!(color(1.0), [80, 40, 173]);
+        assert_eq!(color(2.0), BASE);
+    }
+}
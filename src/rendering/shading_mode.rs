@@ -0,0 +1,6 @@
+#[derive(Debug, Clone, Copy)]
+pub enum ShadingMode {
+    Flat,
+    Gouraud,
+    Phong,
+}
@@ -0,0 +1,137 @@
+use crate::math::geometry::signed_area;
+use crate::math::matrix::Matrix4;
+use crate::math::vector::Vector;
+use crate::rendering::directional_light::DirectionalLight;
+use crate::rendering::point::Point;
+use crate::rendering::rasterizer::rasterize;
+use crate::rendering::shadows::Shadows;
+
+// Depth of the nearest surface as seen from a light, through an orthographic view fitted around the scene
+pub struct ShadowMap {
+    size: u16,
+    depth: Vec<f32>,
+    // World space to the light's clip space, where depth runs 0..1 across the scene
+    light_projection: Matrix4,
+    // World units per unit of stored depth
+    depth_range: f32,
+    texel_size: f32,
+    direction: Vector,
+}
+
+impl ShadowMap {
+    // tris are every face in the scene, in world space
+    pub fn render(light: &DirectionalLight, shadows: &Shadows, tris: &[[Vector; 3]]) -> ShadowMap {
+        let size = shadows.resolution.max(1);
+        let count = (tris.len() * 3).max(1) as f32;
+        let center = tris.iter().flatten().fold(Vector::zero(), |sum, &v| sum + v) / count;
+        let radius = tris.iter().flatten().map(|&v| (v - center).len()).fold(1e-3, f32::max);
+
+        // look_at needs an up vector that isn't parallel to the light
+        let up = if light.direction.y.abs() > 0.99 { Vector::new(0.0, 0.0, 1.0) } else { Vector::new(0.0, 1.0, 0.0) };
+        let view = Matrix4::look_at(center - light.direction * (radius * 2.0), center, up);
+        let projection = Matrix4::orthographic(radius * 2.0, radius * 2.0, radius, radius * 3.0);
+        let mut map = ShadowMap {
+            size,
+            depth: vec![f32::INFINITY; size as usize * size as usize],
+            light_projection: projection * view,
+            depth_range: radius * 2.0,
+            texel_size: radius * 2.0 / size as f32,
+            direction: light.direction,
+        };
+
+        for tri in tris {
+            let projected = tri.map(|v| map.project(v));
+            let mut points = projected.map(|(p, _)| p);
+            let mut depths = projected.map(|(_, d)| d);
+            // Faces turned away from the light still block it, so both windings are drawn
+            if signed_area(points[0], points[1], points[2]) < 0.0 {
+                points.swap(1, 2);
+                depths.swap(1, 2);
+            }
+            rasterize(size, size, points, |x, y, weights| {
+                let depth: f32 = depths.iter().zip(weights).map(|(d, w)| d * w).sum();
+                let stored = &mut map.depth[y as usize * size as usize + x as usize];
+                *stored = stored.min(depth);
+            });
+        }
+        map
+    }
+    // How much of the light reaches a point, from 0 in full shadow to 1. With PCF this is the share of
+    // nearby texels the point is in front of, which softens the shadow's edge.
+    pub fn lit(&self, position: Vector, normal: Vector, shadows: &Shadows) -> f32 {
+        let (point, depth) = self.project(position);
+        let radius = shadows.pcf as i32;
+        // Surfaces at a grazing angle change depth quickly across a texel, so they need more bias, and
+        // more again for the farthest texel PCF compares against
+        let cos = normal.normalized().dot(-self.direction).clamp(0.05, 1.0);
+        let slope = ((1.0 - cos * cos).sqrt() / cos).min(4.0);
+        let bias = (shadows.bias + self.texel_size * slope * (radius + 1) as f32) / self.depth_range;
+
+        let (x, y) = (point.x.floor() as i32, point.y.floor() as i32);
+        let mut lit = 0;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if depth - bias <= self.depth_at(x + dx, y + dy) {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / ((2 * radius + 1) * (2 * radius + 1)) as f32
+    }
+    // Where a world position lands on the map, and its depth from the light
+    fn project(&self, position: Vector) -> (Point, f32) {
+        let [x, y, z, _] = self.light_projection.transform_point([position.x, position.y, position.z, 1.0]);
+        let size = self.size as f32;
+        (Point::new((x + 1.0) / 2.0 * size, (1.0 - y) / 2.0 * size), z)
+    }
+    // Nothing was drawn off the edge of the map, so it never shadows
+    fn depth_at(&self, x: i32, y: i32) -> f32 {
+        if x < 0 || y < 0 || x >= self.size as i32 || y >= self.size as i32 {
+            return f32::INFINITY;
+        }
+        self.depth[y as usize * self.size as usize + x as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::vector::Vector;
+    use crate::rendering::directional_light::DirectionalLight;
+    use crate::rendering::shadows::Shadows;
+    use super::ShadowMap;
+
+    // A square in the plane at height y, as two triangles wound the same way
+    fn square(y: f32, half: f32) -> [[Vector; 3]; 2] {
+        let [a, b, c, d] = [(-half, -half), (half, -half), (half, half), (-half, half)].map(|(x, z)| Vector::new(x, y, z));
+        [[a, b, c], [a, c, d]]
+    }
+
+    fn scene(flip_occluder: bool) -> Vec<[Vector; 3]> {
+        let mut tris = square(0.0, 10.0).to_vec();
+        tris.extend(square(2.0, 2.0).map(|[a, b, c]| if flip_occluder { [a, c, b] } else { [a, b, c] }));
+        tris
+    }
+
+    #[test]
+    fn occluders_shadow_what_is_below_them() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), true);
+        let hard = Shadows::new(64, 0.05, 0, 1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        // Either winding casts a shadow, since faces turned away from the light still block it
+        for flip in [false, true] {
+            let map = ShadowMap::render(&light, &hard, &scene(flip));
+            assert_eq!(map.lit(Vector::new(0.0, 0.0, 0.0), up, &hard), 0.0);
+            assert_eq!(map.lit(Vector::new(1.0, 2.0, -1.0), up, &hard), 1.0);
+            assert_eq!(map.lit(Vector::new(8.0, 0.0, 8.0), up, &hard), 1.0);
+        }
+    }
+
+    #[test]
+    fn filtering_softens_the_shadow_edge() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), true);
+        let soft = Shadows::new(64, 0.05, 2, 1.0);
+        let map = ShadowMap::render(&light, &soft, &scene(false));
+        let lit = map.lit(Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), &soft);
+        assert!(lit > 0.0 && lit < 1.0, "lit {}", lit);
+    }
+}
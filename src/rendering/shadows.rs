@@ -0,0 +1,18 @@
+// How shadow maps are rendered and sampled
+#[derive(Debug, Clone, Copy)]
+pub struct Shadows {
+    // Texels along each side of a light's depth map
+    pub resolution: u16,
+    // World units a surface may be behind the stored depth and still count as lit
+    pub bias: f32,
+    // Radius of the percentage-closer filter in texels, 0 for hard edges
+    pub pcf: u16,
+    // How much light a fully shadowed surface loses
+    pub strength: f32,
+}
+
+impl Shadows {
+    pub fn new(resolution: u16, bias: f32, pcf: u16, strength: f32) -> Self {
+        Self { resolution, bias, pcf, strength }
+    }
+}
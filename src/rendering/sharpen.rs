@@ -0,0 +1,46 @@
+use crate::rendering::post_process::PostProcess;
+use crate::rendering::screen_buffer::ScreenBuffer;
+
+// Unsharp mask: pushes each cell away from the average of its four neighbours
+#[derive(Debug, Clone, Copy)]
+pub struct Sharpen {
+    pub amount: f32,
+}
+
+impl Sharpen {
+    pub fn new(amount: f32) -> Self {
+        Self { amount }
+    }
+}
+
+impl PostProcess for Sharpen {
+    fn apply(&self, buf: &mut ScreenBuffer) {
+        let (width, height) = (buf.width as i32, buf.height as i32);
+        // Read from a copy so cells already sharpened don't feed into their neighbours
+        let mut visible = Vec::with_capacity((width * height) as usize);
+        for y in 0..buf.height {
+            for x in 0..buf.width {
+                visible.push(buf.visible_color(x, y).map(|c| c as f32));
+            }
+        }
+        let at = |x: i32, y: i32| visible[(y.clamp(0, height - 1) * width + x.clamp(0, width - 1)) as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                let center = at(x, y);
+                let neighbours = [at(x - 1, y), at(x + 1, y), at(x, y - 1), at(x, y + 1)];
+                let delta = [0, 1, 2].map(|i| {
+                    let average = neighbours.iter().map(|n| n[i]).sum::<f32>() / 4.0;
+                    (center[i] - average) * self.amount
+                });
+                buf.map_cell_colors(x as u16, y as u16, |color| {
+                    let mut out = color;
+                    for i in 0..3 {
+                        out[i] = (color[i] as f32 + delta[i]).round().clamp(0.0, 255.0) as u8;
+                    }
+                    out
+                });
+            }
+        }
+    }
+}
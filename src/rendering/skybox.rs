@@ -0,0 +1,58 @@
+use std::path::Path;
+use crate::math::vector::Vector;
+use crate::rendering::texture::Texture;
+use crate::rendering::texture_filter::TextureFilter;
+
+// Six images on the inside of a cube around the camera, looked up by view direction
+#[derive(Debug, Clone)]
+pub struct Skybox {
+    // +x, -x, +y, -y, +z, -z, each seen from inside with +y up, or with +z up for the floor and -z up
+    // for the ceiling
+    faces: [Texture; 6],
+}
+
+impl Skybox {
+    pub fn load(paths: [&Path; 6]) -> Result<Skybox, String> {
+        let [px, nx, py, ny, pz, nz] = paths.map(Texture::load);
+        Ok(Skybox { faces: [px?, nx?, py?, ny?, pz?, nz?] })
+    }
+    pub fn sample(&self, direction: Vector, filter: TextureFilter) -> [f32; 3] {
+        let Vector { x, y, z } = direction;
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+        // The face the direction points at most, and where on it, from -1 to 1 each way
+        let (face, u, v) = if ax >= ay && ax >= az {
+            if x > 0.0 { (0, -z / ax, y / ax) } else { (1, z / ax, y / ax) }
+        } else if ay >= az {
+            if y > 0.0 { (2, x / ay, -z / ay) } else { (3, x / ay, z / ay) }
+        } else if z > 0.0 {
+            (4, x / az, y / az)
+        } else {
+            (5, -x / az, y / az)
+        };
+        // Kept off the far edge so sampling doesn't wrap around to the other side of the face
+        let [u, v] = [u, v].map(|t| ((t + 1.0) / 2.0).clamp(0.0, 0.9999));
+        self.faces[face].sample(u, v, filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::vector::Vector;
+    use crate::rendering::texture::Texture;
+    use crate::rendering::texture_filter::TextureFilter;
+    use super::Skybox;
+
+    #[test]
+    fn samples_the_face_a_direction_points_at() {
+        // Each face a single texel coloured by its index
+        let faces = [0, 1, 2, 3, 4, 5].map(|i| Texture::new(1, 1, vec![[i * 40; 3]]).unwrap());
+        let skybox = Skybox { faces };
+        let face = |x: f32, y: f32, z: f32| skybox.sample(Vector::new(x, y, z), TextureFilter::Nearest)[0] / 40.0;
+        assert_eq!(face(1.0, 0.2, -0.3), 0.0);
+        assert_eq!(face(-1.0, 0.9, 0.9), 1.0);
+        assert_eq!(face(0.1, 2.0, 0.0), 2.0);
+        assert_eq!(face(0.0, -1.0, 0.5), 3.0);
+        assert_eq!(face(0.5, 0.5, 1.0), 4.0);
+        assert_eq!(face(0.0, 0.0, -1.0), 5.0);
+    }
+}
@@ -1,24 +1,62 @@
 use std::fmt::{Display, Formatter};
 use termion::color::Color;
-use crate::math::vector::Vector;
+use crate::rendering::text_style::TextStyle;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Stroke {
     pub color: [u8; 3],
     pub tex: char,
+    // Cell background, or whatever is behind the cell where None
+    pub bg: Option<[u8; 3]>,
+    pub style: TextStyle,
 }
 
 impl Stroke {
     pub fn new(color: [u8; 3], tex: char) -> Self {
-        Self { color, tex }
+        Self { color, tex, bg: None, style: TextStyle::default() }
+    }
+    // A space on a background of the colour, so it covers the whole cell
+    pub fn filled(color: [u8; 3]) -> Self {
+        Self::new(color, ' ').with_background(color)
+    }
+    pub fn with_background(&self, bg: [u8; 3]) -> Stroke {
+        Stroke { bg: Some(bg), ..*self }
+    }
+    pub fn with_style(&self, style: TextStyle) -> Stroke {
+        Stroke { style, ..*self }
+    }
+    pub fn is_filled(&self) -> bool {
+        self.tex == ' ' && self.bg.is_some()
+    }
+    // Same glyph and style in another colour. A filled cell's background changes with it.
+    pub fn with_color(&self, color: [u8; 3]) -> Stroke {
+        let bg = if self.is_filled() { Some(color) } else { self.bg };
+        Stroke { color, bg, ..*self }
+    }
+    // The colour the cell mostly shows, or None for a blank one
+    pub fn visible_color(&self) -> Option<[u8; 3]> {
+        match self.tex {
+            ' ' => self.bg,
+            _ => Some(self.color),
+        }
     }
     pub fn as_str(&self) -> String {
         format!("\x1b[38;2;{};{};{}m", self.color[0], self.color[1], self.color[2])
     }
-    pub fn shaded(&self, normal: Vector) -> Stroke {
-        let m = (normal.normalized().dot(Vector::new(0.0, 1.0, 0.0)) + 1.0) / 2.0;
-        let c = self.color.map(|v| (v as f32 * m).round() as u8);
-
-        Self::new(c, self.tex)
+    // Multiplies the colour by another in 0..255, e.g. a texel
+    pub fn modulated(&self, color: [f32; 3]) -> Stroke {
+        let mut c = self.color;
+        for i in 0..3 {
+            c[i] = (c[i] as f32 * color[i] / 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+        self.with_color(c)
+    }
+    // This stroke at the given opacity over a colour
+    pub fn over(&self, under: [u8; 3], alpha: f32) -> Stroke {
+        let mut c = self.color;
+        for (c, u) in c.iter_mut().zip(under) {
+            *c = (u as f32 + (*c as f32 - u as f32) * alpha).round().clamp(0.0, 255.0) as u8;
+        }
+        self.with_color(c)
     }
 }
\ No newline at end of file
@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+// Terminal text attributes a cell is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub dim: bool,
+    pub reverse: bool,
+}
+
+impl TextStyle {
+    // SGR sequence that turns the attributes on, empty when there are none
+    pub fn codes(&self) -> String {
+        let codes: Vec<&str> = [(self.bold, "1"), (self.dim, "2"), (self.reverse, "7")]
+            .into_iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, code)| code)
+            .collect();
+        if codes.is_empty() {
+            return String::new();
+        }
+        format!("\x1b[{}m", codes.join(";"))
+    }
+}
+
+// none, or a comma separated list such as bold,reverse
+impl FromStr for TextStyle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = TextStyle::default();
+        if s == "none" {
+            return Ok(style);
+        }
+        for attribute in s.split(',') {
+            match attribute {
+                "bold" => style.bold = true,
+                "dim" => style.dim = true,
+                "reverse" => style.reverse = true,
+                _ => return Err(format!("unknown text style '{}'", attribute)),
+            }
+        }
+        Ok(style)
+    }
+}
@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::Path;
+use crate::rendering::texture_filter::TextureFilter;
+
+// 8192x8192, to refuse headers that claim absurd sizes
+const MAX_TEXELS: usize = 1 << 26;
+
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    texels: Vec<[u8; 3]>,
+}
+
+impl Texture {
+    pub fn new(width: usize, height: usize, texels: Vec<[u8; 3]>) -> Result<Texture, String> {
+        if width == 0 || height == 0 || texels.len() != width * height {
+            return Err(format!("expected {}x{} texels, found {}", width, height, texels.len()));
+        }
+        Ok(Texture { width, height, texels })
+    }
+    pub fn load(path: &Path) -> Result<Texture, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        Self::from_pnm(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+    // Netpbm images: PGM (P2 ascii, P5 binary) and PPM (P3 ascii, P6 binary). Greyscale is expanded to RGB
+    // and samples are rescaled from the image's maximum value to 0..255.
+    pub fn from_pnm(bytes: &[u8]) -> Result<Texture, String> {
+        let mut pos = 0;
+        let (channels, binary) = match next_token(bytes, &mut pos)? {
+            "P2" => (1, false),
+            "P3" => (3, false),
+            "P5" => (1, true),
+            "P6" => (3, true),
+            magic => return Err(format!("unsupported image type '{}'", magic)),
+        };
+        let width = next_number(bytes, &mut pos)?;
+        let height = next_number(bytes, &mut pos)?;
+        let max = next_number(bytes, &mut pos)?;
+        if max == 0 || max > 65535 {
+            return Err(format!("invalid maximum value {}", max));
+        }
+
+        let texels = match width.checked_mul(height) {
+            Some(texels) if texels <= MAX_TEXELS => texels,
+            _ => return Err(format!("image size {}x{} is too large", width, height)),
+        };
+        let count = texels * channels;
+        let samples: Vec<usize> = if binary {
+            // Exactly one whitespace byte separates the header from the data
+            pos += 1;
+            let size = if max < 256 { 1 } else { 2 };
+            let data = match bytes.get(pos..pos + count * size) {
+                Some(data) => data,
+                None => return Err("image data is truncated".to_string()),
+            };
+            data.chunks(size).map(|c| c.iter().fold(0, |acc, b| acc << 8 | *b as usize)).collect()
+        } else {
+            // Grown as samples are read, so a header claiming a huge image can't allocate before the data runs out
+            let mut samples = Vec::new();
+            for _ in 0..count {
+                samples.push(next_number(bytes, &mut pos)?);
+            }
+            samples
+        };
+
+        let scale = |s: usize| ((s.min(max) * 255 + max / 2) / max) as u8;
+        let texels = samples.chunks(channels).map(|c| match c {
+            [grey] => [scale(*grey); 3],
+            _ => [scale(c[0]), scale(c[1]), scale(c[2])],
+        }).collect();
+        Texture::new(width, height, texels)
+    }
+    fn texel(&self, x: i64, y: i64) -> [f32; 3] {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.texels[y * self.width + x].map(|c| c as f32)
+    }
+    // Coordinates repeat outside 0..1. v = 0 is the bottom row, as in OBJ files.
+    pub fn sample(&self, u: f32, v: f32, filter: TextureFilter) -> [f32; 3] {
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+        match filter {
+            TextureFilter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                // Texel centres sit at half coordinates
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let corners = [
+                    (self.texel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+                    (self.texel(x0 + 1, y0), fx * (1.0 - fy)),
+                    (self.texel(x0, y0 + 1), (1.0 - fx) * fy),
+                    (self.texel(x0 + 1, y0 + 1), fx * fy),
+                ];
+                let mut color = [0.0; 3];
+                for (texel, weight) in corners {
+                    for i in 0..3 {
+                        color[i] += texel[i] * weight;
+                    }
+                }
+                color
+            }
+        }
+    }
+}
+
+// Skips whitespace and # comments, then reads up to the next whitespace
+fn next_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Result<&'a str, String> {
+    loop {
+        match bytes.get(*pos) {
+            Some(b'#') => {
+                while !matches!(bytes.get(*pos), None | Some(b'\n')) {
+                    *pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => *pos += 1,
+            Some(_) => break,
+            None => return Err("unexpected end of image".to_string()),
+        }
+    }
+    let start = *pos;
+    while bytes.get(*pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    std::str::from_utf8(&bytes[start..*pos]).map_err(|e| e.to_string())
+}
+
+fn next_number(bytes: &[u8], pos: &mut usize) -> Result<usize, String> {
+    let token = next_token(bytes, pos)?;
+    token.parse::<usize>().map_err(|_| format!("expected a number, found '{}'", token))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::texture_filter::TextureFilter;
+    use super::Texture;
+
+    // Black, red on the top row and green, blue below
+    fn checker() -> Texture {
+        Texture::from_pnm(b"P3\n# a comment\n2 2 255\n0 0 0  255 0 0\n0 255 0  0 0 255\n").unwrap()
+    }
+
+    #[test]
+    fn reads_ascii_and_binary_images() {
+        let tex = checker();
+        assert_eq!((tex.width, tex.height), (2, 2));
+        assert_eq!(tex.sample(0.75, 0.75, TextureFilter::Nearest), [255.0, 0.0, 0.0]);
+
+        // Binary greyscale with 16 bit samples, rescaled to 0..255
+        let mut bytes = b"P5 2 1 1000\n".to_vec();
+        bytes.extend([0x00, 0x00, 0x01, 0xF4]);
+        let grey = Texture::from_pnm(&bytes).unwrap();
+        assert_eq!(grey.sample(0.75, 0.5, TextureFilter::Nearest), [128.0; 3]);
+    }
+
+    #[test]
+    fn rejects_bad_images() {
+        let bad: [&[u8]; 5] = [
+            b"P4 2 2 1\n",
+            b"P3 2 2 0\n",
+            b"P3 2 2 255\n0 0 0\n",
+            b"P6 2 2 255\n\x00\x00",
+            b"P3 100000 100000 255\n",
+        ];
+        for bytes in bad {
+            assert!(Texture::from_pnm(bytes).is_err(), "accepted {:?}", String::from_utf8_lossy(bytes));
+        }
+    }
+
+    #[test]
+    fn sampling_repeats_and_blends() {
+        let tex = checker();
+        // v = 0 is the bottom row
+        assert_eq!(tex.sample(0.25, 0.25, TextureFilter::Nearest), [0.0, 255.0, 0.0]);
+        assert_eq!(tex.sample(1.25, -0.75, TextureFilter::Nearest), [0.0, 255.0, 0.0]);
+        // Half way between the centres of all four texels
+        assert_eq!(tex.sample(0.5, 0.5, TextureFilter::Bilinear), [63.75, 63.75, 63.75]);
+        // On a texel's centre there's nothing to blend
+        assert_eq!(tex.sample(0.75, 0.25, TextureFilter::Bilinear), [0.0, 0.0, 255.0]);
+    }
+}
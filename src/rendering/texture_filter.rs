@@ -0,0 +1,5 @@
+#[derive(Debug, Clone, Copy)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+}
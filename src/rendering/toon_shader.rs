@@ -0,0 +1,26 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::lambert_shader::LambertShader;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Half-Lambert lighting snapped to a few flat bands, for a cel-shaded look
+#[derive(Debug, Clone, Copy)]
+pub struct ToonShader {
+    pub bands: u8,
+}
+
+impl ToonShader {
+    pub fn new(bands: u8) -> Self {
+        Self { bands: bands.max(2) }
+    }
+}
+
+impl Shader for ToonShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        let intensity = LambertShader::new(true).intensity(input);
+        // Band centres, so the darkest band isn't black and the lightest is full colour
+        let band = (intensity * self.bands as f32).floor().min((self.bands - 1) as f32);
+        let m = (band + 1.0) / self.bands as f32;
+        input.base.with_color(input.base.color.map(|c| (c as f32 * m).round() as u8))
+    }
+}
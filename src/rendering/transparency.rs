@@ -0,0 +1,8 @@
+// How faces with less than full opacity are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transparency {
+    // Sorted far to near and blended one at a time, which is exact unless faces intersect
+    Sorted,
+    // Accumulated in any order with weights favouring the nearer faces, then composited once
+    WeightedBlended,
+}
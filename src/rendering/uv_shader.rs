@@ -0,0 +1,17 @@
+use crate::rendering::fragment_input::FragmentInput;
+use crate::rendering::shader::Shader;
+use crate::rendering::stroke::Stroke;
+
+// Texture coordinates as a colour, u to red and v to green, repeating like a texture would
+#[derive(Debug, Clone, Copy)]
+pub struct UvShader;
+
+impl Shader for UvShader {
+    fn shade(&self, input: &FragmentInput) -> Stroke {
+        let [u, v] = input.uv.map(|t| (t.rem_euclid(1.0) * 255.0).round() as u8);
+        input.base.with_color([u, v, 0])
+    }
+    fn receives_shadows(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,42 @@
+use crate::math::vector::Vector;
+
+// Per-vertex attributes that get interpolated across a triangle
+#[derive(Debug, Clone, Copy)]
+pub struct Varyings {
+    // World space, for looking up shadow maps
+    pub position: Vector,
+    pub normal: Vector,
+    pub uv: [f32; 2],
+    // Distance in front of the camera along its view axis
+    pub depth: f32,
+    // Lit vertex colour for Gouraud shading, filled in just before rasterizing
+    pub color: [f32; 3],
+}
+
+impl Varyings {
+    pub fn new(position: Vector, normal: Vector, uv: [f32; 2], depth: f32) -> Self {
+        Self { position, normal, uv, depth, color: [0.0; 3] }
+    }
+    pub fn zero() -> Self {
+        Self::new(Vector::zero(), Vector::zero(), [0.0; 2], 0.0)
+    }
+    pub fn lerp(&self, other: &Varyings, t: f32) -> Varyings {
+        Varyings::blend(&[*self, *other], [1.0 - t, t])
+    }
+    // Weighted sum of the attributes, for weights that add up to 1
+    pub fn blend<const N: usize>(vs: &[Varyings; N], weights: [f32; N]) -> Varyings {
+        let mut out = Varyings::zero();
+        for (v, w) in vs.iter().zip(weights) {
+            out.position += v.position * w;
+            out.normal += v.normal * w;
+            out.depth += v.depth * w;
+            for i in 0..2 {
+                out.uv[i] += v.uv[i] * w;
+            }
+            for i in 0..3 {
+                out.color[i] += v.color[i] * w;
+            }
+        }
+        out
+    }
+}
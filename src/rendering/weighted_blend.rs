@@ -0,0 +1,149 @@
+use crate::rendering::render_target::RenderTarget;
+use crate::rendering::stroke::Stroke;
+
+// Order independent transparency after McGuire and Bavoil. Transparent faces add their colour into a
+// running sum weighted by opacity and nearness, and the sum is composited over the opaque result in
+// one go, so overlapping faces don't have to be sorted.
+pub struct WeightedBlend {
+    width: u16,
+    height: u16,
+    // Opaque surfaces still hide the transparent ones behind them
+    depth: Vec<f32>,
+    // Colour times alpha times weight, and alpha times weight
+    accum: Vec<[f32; 4]>,
+    // How much of what's behind shows through, the product of 1 - alpha
+    revealage: Vec<f32>,
+    // Nearest transparent surface, whose glyph and style the cell takes
+    front: Vec<Option<(f32, Stroke)>>,
+}
+
+impl WeightedBlend {
+    // Starts empty over a target whose opaque faces are already drawn
+    pub fn over<T: RenderTarget>(target: &T) -> Self {
+        let (width, height) = (target.width(), target.height());
+        let mut depth = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                depth.push(target.depth_at(x, y));
+            }
+        }
+        let size = depth.len();
+        Self {
+            width,
+            height,
+            depth,
+            accum: vec![[0.0; 4]; size],
+            revealage: vec![1.0; size],
+            front: vec![None; size],
+        }
+    }
+    fn index_of(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+    pub fn resolve<T: RenderTarget>(&self, target: &mut T) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = self.index_of(x, y);
+                let Some((depth, front)) = self.front[i] else {
+                    continue;
+                };
+                let [r, g, b, a] = self.accum[i];
+                let color = [r, g, b].map(|c| (c / a.max(1e-5)).round().clamp(0.0, 255.0) as u8);
+                target.blend_pixel(x, y, front.with_color(color), 1.0 - self.revealage[i], depth);
+            }
+        }
+    }
+}
+
+// Grows as depth goes from the far plane to the near one, so nearer faces dominate the average
+fn weight(alpha: f32, depth: f32) -> f32 {
+    alpha * (3e3 * (1.0 - depth.clamp(0.0, 1.0)).powi(3)).max(1e-2)
+}
+
+impl RenderTarget for WeightedBlend {
+    fn width(&self) -> u16 {
+        self.width
+    }
+    fn height(&self) -> u16 {
+        self.height
+    }
+    fn depth_at(&self, x: u16, y: u16) -> f32 {
+        self.depth[self.index_of(x, y)]
+    }
+    fn test_and_set_depth(&mut self, x: u16, y: u16, depth: f32) -> bool {
+        let i = self.index_of(x, y);
+        if depth > self.depth[i] {
+            return false;
+        }
+        self.depth[i] = depth;
+        true
+    }
+    // An opaque write covers whatever was accumulated
+    fn set_pixel(&mut self, x: u16, y: u16, s: Stroke) {
+        let i = self.index_of(x, y);
+        let [r, g, b] = s.color.map(|c| c as f32);
+        self.accum[i] = [r, g, b, 1.0];
+        self.revealage[i] = 0.0;
+        self.front[i] = Some((self.depth[i], s));
+    }
+    fn blend_pixel(&mut self, x: u16, y: u16, s: Stroke, alpha: f32, depth: f32) {
+        let i = self.index_of(x, y);
+        let w = weight(alpha, depth);
+        for (acc, c) in self.accum[i].iter_mut().zip(s.color) {
+            *acc += c as f32 * w;
+        }
+        self.accum[i][3] += w;
+        self.revealage[i] *= 1.0 - alpha;
+        if self.front[i].is_none_or(|(front, _)| depth < front) {
+            self.front[i] = Some((depth, s));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::render_target::RenderTarget;
+    use crate::rendering::screen_buffer::ScreenBuffer;
+    use crate::rendering::stroke::Stroke;
+    use super::WeightedBlend;
+
+    // One cell with an opaque green face drawn at depth 0.9
+    fn opaque() -> ScreenBuffer {
+        let mut screen = ScreenBuffer::new(1, 1);
+        screen.test_and_set_depth(0, 0, 0.9);
+        screen.set_pixel(0, 0, Stroke::new([0, 255, 0], '#'));
+        screen
+    }
+
+    fn composite(layers: &[([u8; 3], f32, f32)]) -> Stroke {
+        let mut screen = opaque();
+        let mut blend = WeightedBlend::over(&screen);
+        for &(color, alpha, depth) in layers {
+            if blend.test_depth(0, 0, depth) {
+                blend.blend_pixel(0, 0, Stroke::new(color, '*'), alpha, depth);
+            }
+        }
+        blend.resolve(&mut screen);
+        screen.get_pixel(0, 0)
+    }
+
+    #[test]
+    fn one_layer_blends_over_the_opaque_result() {
+        let cell = composite(&[([255; 3], 0.5, 0.5)]);
+        assert_eq!((cell.color, cell.tex), ([128, 255, 128], '*'));
+        // Behind the opaque face it's hidden
+        assert_eq!(composite(&[([255; 3], 0.5, 0.95)]).color, [0, 255, 0]);
+    }
+
+    #[test]
+    fn layers_composite_the_same_in_any_order() {
+        let near = ([255, 0, 0], 0.5, 0.2);
+        let far = ([0, 0, 255], 0.5, 0.6);
+        let forward = composite(&[near, far]);
+        let backward = composite(&[far, near]);
+        assert_eq!(forward.color, backward.color);
+        // Three quarters covered, with the nearer layer weighted more
+        assert_eq!(forward.color[1], 64);
+        assert!(forward.color[0] > forward.color[2]);
+    }
+}